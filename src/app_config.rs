@@ -1,6 +1,8 @@
+use crate::i18n::{catalog, fill, set_language, tr, Language};
 use anyhow::{Context, Result};
 use derive_new::new;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -8,14 +10,33 @@ fn default_config_version() -> u32 {
     1
 }
 
-#[derive(Debug, Deserialize, Serialize, new)]
+#[derive(Debug, Deserialize, Serialize, Clone, new)]
 pub struct AppConfig {
     #[serde(default = "default_config_version")]
     pub config_version: u32,
+    /// UI language and the language of generated messages
+    #[serde(default)]
+    pub language: Language,
     pub llm_config: LLMConfig,
+    #[serde(default)]
+    pub diff: DiffConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub context: ContextConfig,
+    /// Issue tracker used to add the issue title to the prompt
+    #[serde(default)]
+    pub tracker: Option<TrackerConfig>,
+    #[serde(default)]
+    #[new(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    #[new(default)]
+    pub usage: UsageConfig,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum LLMProvider {
     OpenAI,
     /// Anthropic API provider (Claude models)
@@ -36,32 +57,338 @@ pub enum LLMProvider {
     CUSTOM,
 }
 
-#[derive(Debug, Deserialize, Serialize, new)]
+impl LLMProvider {
+    pub const ALL: [LLMProvider; 9] = [
+        LLMProvider::OpenAI,
+        LLMProvider::Anthropic,
+        LLMProvider::Ollama,
+        LLMProvider::DeepSeek,
+        LLMProvider::XAI,
+        LLMProvider::Phind,
+        LLMProvider::Google,
+        LLMProvider::Groq,
+        LLMProvider::CUSTOM,
+    ];
+
+    /// Case-insensitive name as written in the config file
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|provider| format!("{:?}", provider).eq_ignore_ascii_case(name))
+    }
+
+    /// Environment variable that conventionally holds the API key
+    pub fn api_key_env(&self) -> Option<&'static str> {
+        match self {
+            LLMProvider::OpenAI => Some("OPENAI_API_KEY"),
+            LLMProvider::Anthropic => Some("ANTHROPIC_API_KEY"),
+            LLMProvider::DeepSeek => Some("DEEPSEEK_API_KEY"),
+            LLMProvider::XAI => Some("XAI_API_KEY"),
+            LLMProvider::Google => Some("GEMINI_API_KEY"),
+            LLMProvider::Groq => Some("GROQ_API_KEY"),
+            LLMProvider::Ollama | LLMProvider::Phind | LLMProvider::CUSTOM => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, new)]
 pub struct LLMConfig {
     pub provider: LLMProvider,
     pub enable: bool,
     pub api_key: Option<String>,
+    /// For CUSTOM, the API base (`https://host/v1`) or the full chat completions URL
     pub url: Option<String>,
     pub model: Option<String>,
+    /// Extra request headers for CUSTOM, e.g. Azure's `api-key` or an org header
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[new(default)]
+    pub headers: HashMap<String, String>,
+    /// Query parameters for CUSTOM, e.g. `api-version`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[new(default)]
+    pub query: HashMap<String, String>,
+    /// Proxy for CUSTOM requests, instead of `HTTPS_PROXY`
+    #[serde(default)]
+    #[new(default)]
+    pub proxy: Option<String>,
+    /// PEM file with extra root certificates for CUSTOM, e.g. a corporate CA
+    #[serde(default)]
+    #[new(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// Request and response schema of a CUSTOM endpoint
+    #[serde(default, skip_serializing_if = "WireFormat::is_openai")]
+    #[new(default)]
+    pub wire_format: WireFormat,
+    /// Send CUSTOM requests to `url` as written, without completing the endpoint path
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[new(default)]
+    pub url_verbatim: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum WireFormat {
+    /// OpenAI chat completions
+    #[default]
+    OpenAI,
+    /// Anthropic Messages (`/v1/messages`)
+    Anthropic,
+    /// Gemini `generateContent`
+    Gemini,
+}
+
+impl WireFormat {
+    pub const ALL: [WireFormat; 3] = [
+        WireFormat::OpenAI,
+        WireFormat::Anthropic,
+        WireFormat::Gemini,
+    ];
+
+    fn is_openai(&self) -> bool {
+        *self == WireFormat::OpenAI
+    }
+}
+
+fn default_diff_ignore() -> Vec<String> {
+    [
+        "Cargo.lock",
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "poetry.lock",
+        "go.sum",
+        "*.min.js",
+        "*.min.css",
+        "dist/**",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_diff_max_tokens() -> usize {
+    6000
+}
+
+/// Controls how the staged diff is turned into prompt text
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DiffConfig {
+    /// Globs whose content is replaced by a one-line note (lockfiles, bundles, ...)
+    #[serde(default = "default_diff_ignore")]
+    pub ignore: Vec<String>,
+    /// Rough token budget for the diff, 0 disables the limit
+    #[serde(default = "default_diff_max_tokens")]
+    pub max_tokens: usize,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        Self {
+            ignore: default_diff_ignore(),
+            max_tokens: default_diff_max_tokens(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub enum RedactionMode {
+    /// Send the diff as-is
+    Off,
+    /// Replace detected secrets with `[REDACTED:<kind>]`
+    #[default]
+    Mask,
+    /// Abort before calling the LLM when any secret is detected
+    Refuse,
+}
+
+fn default_entropy_threshold() -> f64 {
+    4.5
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub mode: RedactionMode,
+    /// Extra regexes to mask in addition to the built-in secret patterns
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Shannon entropy (bits/char) above which long tokens are masked, 0 disables
+    #[serde(default = "default_entropy_threshold")]
+    pub entropy_threshold: f64,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            mode: RedactionMode::default(),
+            patterns: Vec::new(),
+            entropy_threshold: default_entropy_threshold(),
+        }
+    }
+}
+
+pub(crate) fn default_true() -> bool {
+    true
+}
+
+fn default_context_history() -> usize {
+    10
+}
+
+/// Optional project context added to the prompt, all counted against `diff.max_tokens`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ContextConfig {
+    /// Number of recent commit subjects to include, 0 disables
+    #[serde(default = "default_context_history")]
+    pub history: usize,
+    /// Include the current branch name
+    #[serde(default = "default_true")]
+    pub branch: bool,
+    /// Include `.gcw/context.md` from the repo root
+    #[serde(default = "default_true")]
+    pub project_notes: bool,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            history: default_context_history(),
+            branch: default_true(),
+            project_notes: default_true(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub enum TrackerKind {
+    GitHub,
+    GitLab,
+    Jira,
+    /// JSON file of `{"<id>": "<title>"}`, for offline use
+    Local,
+}
+
+fn default_tracker_cache_ttl_hours() -> u64 {
+    24 * 7
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TrackerConfig {
+    pub kind: TrackerKind,
+    /// API base URL, defaults to the public GitHub/GitLab instance
+    pub base_url: Option<String>,
+    pub token: Option<String>,
+    /// GitHub `owner/name` or GitLab project path, defaults to the `origin` remote
+    pub project: Option<String>,
+    /// Jira Cloud account email for basic auth
+    pub user: Option<String>,
+    /// JSON file for the `Local` tracker
+    pub path: Option<PathBuf>,
+    #[serde(default = "default_tracker_cache_ttl_hours")]
+    pub cache_ttl_hours: u64,
+}
+
+fn default_cache_ttl_hours() -> u64 {
+    24
+}
+
+fn default_cache_max_entries() -> usize {
+    200
+}
+
+/// On-disk cache of generated messages, so regenerating for the same diff is free
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CacheConfig {
+    #[serde(default = "default_true")]
+    pub enable: bool,
+    #[serde(default = "default_cache_ttl_hours")]
+    pub ttl_hours: u64,
+    /// Oldest responses are dropped beyond this many
+    #[serde(default = "default_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enable: default_true(),
+            ttl_hours: default_cache_ttl_hours(),
+            max_entries: default_cache_max_entries(),
+        }
+    }
+}
+
+/// USD per million tokens
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// How `gcw usage` prices the recorded calls
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct UsageConfig {
+    /// By model name, e.g. `prices."gpt-4o" = { input = 2.5, output = 10.0 }`
+    #[serde(default)]
+    pub prices: HashMap<String, ModelPrice>,
 }
 
 impl LLMConfig {
+    /// The configured key, else the provider's conventional environment variable
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key.clone().or_else(|| {
+            self.provider
+                .api_key_env()
+                .and_then(|name| std::env::var(name).ok())
+        })
+    }
+
     pub fn is_custom(&self) -> bool {
         matches!(self.provider, LLMProvider::CUSTOM)
     }
+
+    /// Whether prompts stay on this machine (Ollama or a custom endpoint, on loopback)
+    pub fn is_local(&self) -> bool {
+        match self.provider {
+            LLMProvider::Ollama => {
+                is_loopback_url(self.url.as_deref().unwrap_or(crate::ollama::DEFAULT_URL))
+            }
+            LLMProvider::CUSTOM => self.url.as_deref().is_some_and(is_loopback_url),
+            _ => false,
+        }
+    }
+}
+
+fn is_loopback_url(url: &str) -> bool {
+    let host = url
+        .split("://")
+        .nth(1)
+        .unwrap_or(url)
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default();
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self::new(
             1,
+            Language::default(),
             LLMConfig::new(LLMProvider::OpenAI, false, None, None, None),
+            DiffConfig::default(),
+            RedactionConfig::default(),
+            ContextConfig::default(),
+            None,
         )
     }
 }
 
 // 核心逻辑：自定义 macOS 的配置目录
-fn get_config_dir() -> Result<PathBuf> {
+pub fn get_config_dir() -> Result<PathBuf> {
     let app_name = "aigcw";
     #[cfg(target_os = "macos")]
     {
@@ -77,7 +404,7 @@ fn get_config_dir() -> Result<PathBuf> {
     }
 }
 
-fn get_config_path() -> anyhow::Result<PathBuf> {
+pub fn get_config_path() -> anyhow::Result<PathBuf> {
     let config_dir = get_config_dir()?;
 
     fs::create_dir_all(&config_dir)?;
@@ -86,26 +413,82 @@ fn get_config_path() -> anyhow::Result<PathBuf> {
 
 pub fn load_app_config() -> anyhow::Result<AppConfig> {
     let config_path = get_config_path()?;
+    // 不存在时创建默认配置
+    init_default_config(&config_path)?;
 
-    let config = if config_path.exists() {
-        let config_str = fs::read_to_string(&config_path)?;
-        config::Config::builder()
-            .add_source(config::File::from_str(
-                &config_str,
-                config::FileFormat::Toml,
-            ))
-            .build()?
-            .try_deserialize()?
-    } else {
-        // 创建默认配置
-        let default_config = AppConfig::default();
-        fs::write(config_path, toml::to_string(&default_config)?)?;
-        default_config
-    };
+    let config_str = fs::read_to_string(&config_path)?;
+    let config = config::Config::builder()
+        .add_source(config::File::from_str(
+            &config_str,
+            config::FileFormat::Toml,
+        ))
+        .build()?
+        .try_deserialize::<AppConfig>()?;
+    set_language(config.language);
 
     Ok(config)
 }
 
+/// Rewrite the `[llm_config]` tables of the config file with `llm_config`, keeping the
+/// other settings and their comments as they are
+pub fn save_llm_config(llm_config: &LLMConfig) -> anyhow::Result<PathBuf> {
+    #[derive(Serialize)]
+    struct Tables<'a> {
+        llm_config: &'a LLMConfig,
+    }
+    let config_path = get_config_path()?;
+    let content = fs::read_to_string(&config_path).unwrap_or_default();
+    let tables = toml::to_string_pretty(&Tables { llm_config })?;
+    fs::write(&config_path, replace_table(&content, "llm_config", &tables))
+        .with_context(|| fill(tr().write_config_failed, &[&config_path.display()]))?;
+    Ok(config_path)
+}
+
+/// `content` with the table `name` and its subtables replaced by `tables`, or with
+/// `tables` appended when it has none. Comments just above the next table stay with it.
+fn replace_table(content: &str, name: &str, tables: &str) -> String {
+    let owned = |header: &str| {
+        let header = header.trim_matches(|c| c == '[' || c == ']').trim();
+        header == name
+            || header
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    let mut out = String::new();
+    let mut inside = false;
+    let mut replaced = false;
+    let mut trailing = String::new();
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            let was_inside = inside;
+            inside = owned(trimmed);
+            if inside && !replaced {
+                out.push_str(tables);
+                replaced = true;
+            }
+            if was_inside && !inside {
+                out.push_str(&trailing);
+            }
+            trailing.clear();
+        }
+        if !inside {
+            out.push_str(line);
+        } else if trimmed.is_empty() || trimmed.starts_with('#') {
+            trailing.push_str(line);
+        } else {
+            trailing.clear();
+        }
+    }
+    if !replaced {
+        if !out.is_empty() {
+            out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        out.push_str(tables);
+    }
+    out
+}
+
 fn init_default_config(config_path: &PathBuf) -> Result<()> {
     if config_path.exists() {
         // 已有配置文件时检查版本
@@ -117,23 +500,30 @@ fn init_default_config(config_path: &PathBuf) -> Result<()> {
 
         if existing.config_version < AppConfig::default().config_version {
             println!(
-                "发现旧版配置文件 (v{})，正在升级到 v{}...",
-                existing.config_version,
-                AppConfig::default().config_version
+                "{}",
+                fill(
+                    catalog(existing.language).config_upgrade,
+                    &[
+                        &existing.config_version,
+                        &AppConfig::default().config_version
+                    ]
+                )
             );
             // add version migrate logic
         }
         return Ok(());
     }
 
+    // No language is configured yet, so errors use the one the default config gets
+    let text = catalog(Language::default());
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent)
-            .with_context(|| format!("无法创建配置目录: {}", parent.display()))?;
+            .with_context(|| fill(text.create_config_dir_failed, &[&parent.display()]))?;
     }
 
     let default_config = toml::to_string_pretty(&AppConfig::default())?;
     fs::write(config_path, default_config)
-        .with_context(|| format!("无法写入配置文件: {}", config_path.display()))?;
+        .with_context(|| fill(text.write_config_failed, &[&config_path.display()]))?;
 
     Ok(())
 }
@@ -141,7 +531,51 @@ fn init_default_config(config_path: &PathBuf) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio;
+
+    #[test]
+    fn test_replace_table_keeps_other_settings() {
+        let content = "\
+language = \"en\" # UI language
+
+[llm_config]
+provider = \"OpenAI\"
+enable = false
+
+[llm_config.headers]
+\"api-key\" = \"old\"
+
+# Files left out of the prompt
+[diff]
+ignore = [\"dist/**\"] # build output
+";
+        let tables = "[llm_config]\nprovider = \"Ollama\"\nenable = true\n";
+        assert_eq!(
+            replace_table(content, "llm_config", tables),
+            "\
+language = \"en\" # UI language
+
+[llm_config]
+provider = \"Ollama\"
+enable = true
+
+# Files left out of the prompt
+[diff]
+ignore = [\"dist/**\"] # build output
+"
+        );
+        assert_eq!(
+            replace_table("language = \"en\"\n", "llm_config", tables),
+            format!("language = \"en\"\n\n{}", tables)
+        );
+    }
+
+    #[test]
+    fn test_parse_provider() {
+        assert_eq!(LLMProvider::parse("ollama"), Some(LLMProvider::Ollama));
+        assert_eq!(LLMProvider::parse("custom"), Some(LLMProvider::CUSTOM));
+        assert_eq!(LLMProvider::parse("xai"), Some(LLMProvider::XAI));
+        assert_eq!(LLMProvider::parse("mistral"), None);
+    }
 
     #[tokio::test]
     async fn test_get_config_path() {
@@ -0,0 +1,123 @@
+use crate::commit_types::BranchConfig;
+
+/// Lowercase ASCII words joined by `-`, e.g. `Add retry to custom backend!` → `add-retry-to-custom-backend`
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Cut `slug` to at most `max` bytes on a word boundary
+fn truncate_slug(slug: &str, max: usize) -> &str {
+    if slug.len() <= max {
+        return slug;
+    }
+    match slug[..=max].rfind('-') {
+        Some(idx) if idx > 0 => &slug[..idx],
+        _ => &slug[..max],
+    }
+}
+
+/// Fill `{type}`, `{issue}` and `{slug}` in the configured pattern.
+///
+/// Segments left empty by a missing issue are dropped (`feat/-retry` → `feat/retry`),
+/// and the slug is shortened so the name fits `max_length`. `None` when the slug is empty.
+pub fn branch_name(
+    config: &BranchConfig,
+    commit_type: &str,
+    issue: Option<&str>,
+    slug: &str,
+) -> Option<String> {
+    let slug = slugify(slug);
+    if slug.is_empty() {
+        return None;
+    }
+    let issue = issue
+        .map(|i| i.trim().trim_start_matches('#'))
+        .filter(|i| !i.is_empty())
+        .map(|i| {
+            i.chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    let render = |slug: &str| {
+        let name = config
+            .pattern
+            .replace("{type}", &slugify(commit_type))
+            .replace("{issue}", &issue)
+            .replace("{slug}", slug);
+        name.split('/')
+            .map(|segment| {
+                segment
+                    .split('-')
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("-")
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let overhead = render("x").len().saturating_sub(1);
+    let budget = config.max_length.saturating_sub(overhead).max(8);
+    Some(render(truncate_slug(&slug, budget)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pattern: &str) -> BranchConfig {
+        BranchConfig {
+            pattern: pattern.into(),
+            max_length: 40,
+        }
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Add retry to CUSTOM backend!"),
+            "add-retry-to-custom-backend"
+        );
+        assert_eq!(
+            slugify("  --fix: user's @home/path.. "),
+            "fix-user-s-home-path"
+        );
+    }
+
+    #[test]
+    fn test_branch_name_pattern() {
+        let pattern = config("{type}/{issue}-{slug}");
+        assert_eq!(
+            branch_name(&pattern, "feat", Some("#12"), "add retry").as_deref(),
+            Some("feat/12-add-retry")
+        );
+        assert_eq!(
+            branch_name(&pattern, "fix", None, "Crash on start").as_deref(),
+            Some("fix/crash-on-start")
+        );
+        assert_eq!(
+            branch_name(&pattern, "feat", Some("PROJ-7"), "x").as_deref(),
+            Some("feat/PROJ-7-x")
+        );
+        assert_eq!(branch_name(&pattern, "feat", None, "重试"), None);
+    }
+
+    #[test]
+    fn test_branch_name_truncates_slug() {
+        let name = branch_name(
+            &config("{type}/{slug}"),
+            "feat",
+            None,
+            "add retry with exponential backoff to the custom backend",
+        )
+        .unwrap();
+        assert_eq!(name, "feat/add-retry-with-exponential-backoff");
+        assert!(name.len() <= 40);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::diff::StagedDiff;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Public Rust item declared on a diff line, e.g. `pub async fn load(`
+fn pub_item_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"^\s*pub\s+(?:(?:async|const|unsafe|extern\s+"[^"]*")\s+)*(fn|struct|enum|trait|type|const|static|mod|union)\s+([A-Za-z_][A-Za-z0-9_]*)"#,
+        )
+        .unwrap()
+    })
+}
+
+/// Whitespace-insensitive form of a declaration line, used to compare signatures
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Likely API breaks in `diff`: `pub` items that were removed or whose
+/// declaration line changed.
+///
+/// Items are matched by kind and name across all files, so moving an item to
+/// another module is not reported. Only Rust sources are inspected.
+pub fn detect(diff: &StagedDiff) -> Vec<String> {
+    let regex = pub_item_regex();
+    let mut removed = Vec::new();
+    let mut added: HashMap<(String, String), Vec<String>> = HashMap::new();
+
+    for file in diff.files.iter().filter(|f| f.path.ends_with(".rs")) {
+        let lines = file.hunks.iter().flat_map(|h| &h.lines);
+        for line in lines {
+            let (marker, code) = line.split_at(line.len().min(1));
+            let Some(caps) = regex.captures(code) else {
+                continue;
+            };
+            let key = (caps[1].to_string(), caps[2].to_string());
+            match marker {
+                "-" => removed.push((file.path.clone(), key, normalize(code))),
+                "+" => added.entry(key).or_default().push(normalize(code)),
+                _ => {}
+            }
+        }
+    }
+
+    removed
+        .into_iter()
+        .filter_map(|(path, (kind, name), declaration)| {
+            match added.get(&(kind.clone(), name.clone())) {
+                None => Some(format!("{}: removed pub {} {}", path, kind, name)),
+                Some(new) if kind == "fn" && !new.contains(&declaration) => {
+                    Some(format!("{}: changed signature of pub fn {}", path, name))
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_raw(raw: &str) -> Vec<String> {
+        detect(&StagedDiff::parse(raw))
+    }
+
+    #[test]
+    fn test_detects_removed_and_changed_items() {
+        let findings = detect_raw(
+            "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,5 +1,4 @@
+-pub fn load(path: &Path) -> Config {
++pub fn load(path: &Path, strict: bool) -> Config {
+-pub struct Legacy;
+-pub(crate) fn helper() {}
+ pub fn untouched() {}
+",
+        );
+        assert_eq!(
+            findings,
+            [
+                "src/lib.rs: changed signature of pub fn load",
+                "src/lib.rs: removed pub struct Legacy",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignores_moves_and_other_languages() {
+        let findings = detect_raw(
+            "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +0,0 @@
+-pub async fn   fetch(id: u32) {}
+diff --git a/src/b.rs b/src/b.rs
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -0,0 +1 @@
++pub async fn fetch(id: u32) {}
+diff --git a/notes.md b/notes.md
+--- a/notes.md
++++ b/notes.md
+@@ -1 +0,0 @@
+-pub fn example() {}
+",
+        );
+        assert!(findings.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::app_config::{get_config_dir, CacheConfig, LLMConfig};
+use crate::prompt::RenderedPrompt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Skip lookups for this run (`--no-cache`); fresh replies still replace cached ones
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    reply: String,
+    created_at: u64,
+}
+
+/// LLM replies on disk, keyed by a hash of the provider, model and full prompt
+pub struct ResponseCache {
+    path: PathBuf,
+    ttl_secs: u64,
+    max_entries: usize,
+}
+
+impl ResponseCache {
+    pub fn new(path: PathBuf, ttl_secs: u64, max_entries: usize) -> Self {
+        Self {
+            path,
+            ttl_secs,
+            max_entries,
+        }
+    }
+
+    /// The cache in the config directory, `None` when disabled
+    pub fn open(config: &CacheConfig) -> anyhow::Result<Option<Self>> {
+        if !config.enable {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(
+            cache_path()?,
+            config.ttl_hours * 3600,
+            config.max_entries,
+        )))
+    }
+
+    /// Hash of everything that decides which endpoint answers and what it is asked
+    pub fn key(llm_config: &LLMConfig, prompt: &RenderedPrompt) -> String {
+        let sorted =
+            |map: &HashMap<String, String>| format!("{:?}", map.iter().collect::<BTreeMap<_, _>>());
+        let mut hasher = Sha256::new();
+        for part in [
+            format!("{:?}", llm_config.provider),
+            llm_config.url.clone().unwrap_or_default(),
+            llm_config.model.clone().unwrap_or_default(),
+            format!("{:?}", llm_config.wire_format),
+            llm_config.url_verbatim.to_string(),
+            sorted(&llm_config.headers),
+            sorted(&llm_config.query),
+            prompt.system.clone(),
+            prompt.user.clone(),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn read(&self) -> HashMap<String, CacheEntry> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, entries: &HashMap<String, CacheEntry>) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Written next to the cache and renamed over it, so concurrent runs never
+        // read a half-written file
+        let tmp = self
+            .path
+            .with_extension(format!("json.{}.tmp", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string(entries)?)?;
+        if let Err(e) = std::fs::rename(&tmp, &self.path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Reply stored for `key`, if it has not expired
+    pub fn get(&self, key: &str) -> Option<String> {
+        if DISABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.read()
+            .remove(key)
+            .filter(|entry| now_secs().saturating_sub(entry.created_at) < self.ttl_secs)
+            .map(|entry| entry.reply)
+    }
+
+    /// Store `reply`, dropping expired entries and the oldest ones over the limit
+    pub fn put(&self, key: &str, reply: &str) -> anyhow::Result<()> {
+        let now = now_secs();
+        let mut entries = self.read();
+        entries.retain(|_, entry| now.saturating_sub(entry.created_at) < self.ttl_secs);
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                reply: reply.to_string(),
+                created_at: now,
+            },
+        );
+        if entries.len() > self.max_entries {
+            let mut by_age: Vec<(u64, String)> = entries
+                .iter()
+                .map(|(key, entry)| (entry.created_at, key.clone()))
+                .collect();
+            by_age.sort();
+            let excess = entries.len() - self.max_entries;
+            for (_, key) in by_age.into_iter().take(excess) {
+                entries.remove(&key);
+            }
+        }
+        self.write(&entries)
+    }
+
+    /// Remove every entry, returning how many there were
+    pub fn clear(&self) -> anyhow::Result<usize> {
+        let count = self.read().len();
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(count)
+    }
+}
+
+pub fn cache_path() -> anyhow::Result<PathBuf> {
+    Ok(get_config_dir()?.join("cache").join("responses.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::LLMProvider;
+
+    fn prompt(user: &str) -> RenderedPrompt {
+        RenderedPrompt {
+            system: "system".into(),
+            user: user.into(),
+        }
+    }
+
+    #[test]
+    fn test_key_covers_endpoint_and_prompt() {
+        let mut llm = LLMConfig::new(LLMProvider::OpenAI, true, None, None, None);
+        let key = ResponseCache::key(&llm, &prompt("diff"));
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, ResponseCache::key(&llm, &prompt("diff")));
+        assert_ne!(key, ResponseCache::key(&llm, &prompt("other diff")));
+        llm.model = Some("gpt-4o".into());
+        assert_ne!(key, ResponseCache::key(&llm, &prompt("diff")));
+        let key = ResponseCache::key(&llm, &prompt("diff"));
+        llm.query.insert("api-version".into(), "2024-06-01".into());
+        assert_ne!(key, ResponseCache::key(&llm, &prompt("diff")));
+    }
+
+    #[test]
+    fn test_put_get_and_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path().join("responses.json"), 3600, 2);
+        cache.put("a", "first").unwrap();
+        assert_eq!(cache.get("a").as_deref(), Some("first"));
+        assert_eq!(cache.get("b"), None);
+
+        // entries past the TTL are ignored
+        let mut entries = cache.read();
+        entries.get_mut("a").unwrap().created_at = 0;
+        cache.write(&entries).unwrap();
+        assert_eq!(cache.get("a"), None);
+
+        cache.put("b", "second").unwrap();
+        cache.put("c", "third").unwrap();
+        let mut entries = cache.read();
+        entries.get_mut("b").unwrap().created_at -= 10;
+        cache.write(&entries).unwrap();
+        cache.put("d", "fourth").unwrap();
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c").as_deref(), Some("third"));
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get("d"), None);
+    }
+}
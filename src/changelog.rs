@@ -0,0 +1,451 @@
+use crate::commit_types::Config;
+use crate::git_utils::{get_last_tag, get_log, get_origin_url, get_repo_root, LogEntry};
+use crate::i18n::tr;
+use crate::issue::issue_reference;
+use regex::Regex;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Header written when the changelog file does not exist yet
+const CHANGELOG_HEADER: &str = "\
+# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+/// A commit whose subject follows `type(scope)!: subject`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    pub hash: String,
+    /// `None` for subjects that are not conventional commits
+    pub commit_type: Option<String>,
+    pub scope: Option<String>,
+    pub subject: String,
+    /// `Some` for breaking changes, with the `BREAKING CHANGE:` text if there is one
+    pub breaking: Option<String>,
+    pub issues: Vec<String>,
+}
+
+fn header_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^(\w+)(?:\(([^)]*)\))?(!)?:\s*(.*)$").unwrap())
+}
+
+fn footer_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"(?im)^(?:close[sd]?|fix(?:e[sd])?|resolve[sd]?|refs?)\s*:?\s+(#?[A-Za-z0-9-]+)",
+        )
+        .unwrap()
+    })
+}
+
+/// Scopes that hold an issue reference rather than a component name
+fn is_issue_scope(scope: &str) -> bool {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX
+        .get_or_init(|| Regex::new(r"^(?:#?\d+|[A-Z][A-Z0-9]*-\d+)$").unwrap())
+        .is_match(scope)
+}
+
+impl ConventionalCommit {
+    pub fn parse(entry: &LogEntry, config: &Config) -> Self {
+        let mut commit = Self {
+            hash: entry.hash.clone(),
+            commit_type: None,
+            scope: None,
+            subject: entry.subject.trim().to_string(),
+            breaking: None,
+            issues: Vec::new(),
+        };
+
+        if let Some(caps) = header_regex().captures(&entry.subject) {
+            let commit_type = caps[1].to_string();
+            let mut subject = caps[4].trim();
+            // Drop the emoji gcw puts in front of the subject
+            if let Some(t) = config.types.iter().find(|t| t.name == commit_type) {
+                if !t.emoji.is_empty() {
+                    subject = subject
+                        .strip_prefix(t.emoji.as_str())
+                        .unwrap_or(subject)
+                        .trim();
+                }
+            }
+            commit.subject = subject.to_string();
+            match caps.get(2).map(|m| m.as_str().trim()) {
+                Some(scope) if is_issue_scope(scope) => commit.issues.push(scope.to_string()),
+                Some(scope) if !scope.is_empty() => commit.scope = Some(scope.to_string()),
+                _ => {}
+            }
+            if caps.get(3).is_some() {
+                commit.breaking = Some(String::new());
+            }
+            commit.commit_type = Some(commit_type);
+        }
+
+        for line in entry.body.lines() {
+            let note = line
+                .strip_prefix("BREAKING CHANGE:")
+                .or_else(|| line.strip_prefix("BREAKING-CHANGE:"));
+            if let Some(note) = note {
+                commit.breaking = Some(note.trim().to_string());
+            }
+        }
+        for caps in footer_regex().captures_iter(&entry.body) {
+            let issue = caps[1].to_string();
+            if !commit.issues.contains(&issue) {
+                commit.issues.push(issue);
+            }
+        }
+        commit
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+/// `git@github.com:owner/name.git` → `https://github.com/owner/name`
+pub fn web_url(remote: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches(".git");
+    let (host, path) = if let Some((_, rest)) = remote.split_once("://") {
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, r)| r);
+        rest.split_once('/')?
+    } else {
+        let rest = remote.rsplit_once('@').map_or(remote, |(_, r)| r);
+        rest.split_once(':')?
+    };
+    // drop an explicit port, e.g. `ssh://git@host:2222/owner/name`
+    let host = host.split(':').next()?;
+    Some(format!("https://{}/{}", host, path.trim_matches('/')))
+}
+
+/// Commits in `from..to`, newest first; `from` defaults to the last tag before `to`
+pub fn collect_commits(
+    config: &Config,
+    from: Option<&str>,
+    to: &str,
+) -> anyhow::Result<Vec<ConventionalCommit>> {
+    let from = match from {
+        Some(from) => Some(from.to_string()),
+        // a tag at `to` itself belongs to the range being described
+        None if to == "HEAD" => get_last_tag(to, &config.release.tag_prefix),
+        None => get_last_tag(&format!("{}^", to), &config.release.tag_prefix),
+    };
+    let range = match from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+    Ok(get_log(&range)?
+        .iter()
+        .map(|entry| ConventionalCommit::parse(entry, config))
+        .collect())
+}
+
+/// Renders commits as one Keep a Changelog section
+pub struct ChangelogWriter<'a> {
+    pub config: &'a Config,
+    /// Link template with an `{id}` placeholder, e.g. `https://github.com/o/n/issues/{id}`
+    pub issue_url: Option<String>,
+}
+
+impl<'a> ChangelogWriter<'a> {
+    /// Uses `[changelog] issue_url`, or the issues page of the `origin` remote
+    pub fn for_repo(config: &'a Config) -> Self {
+        let issue_url = config.changelog.issue_url.clone().or_else(|| {
+            get_origin_url()
+                .and_then(|remote| web_url(&remote))
+                .map(|url| format!("{}/issues/{{id}}", url))
+        });
+        Self { config, issue_url }
+    }
+
+    fn issue_link(&self, issue: &str) -> String {
+        let reference = issue_reference(issue);
+        match &self.issue_url {
+            Some(url) => format!(
+                "[{}]({})",
+                reference,
+                url.replace("{id}", reference.trim_start_matches('#'))
+            ),
+            None => reference,
+        }
+    }
+
+    fn entry(&self, commit: &ConventionalCommit, text: &str) -> String {
+        let mut line = String::from("- ");
+        if let Some(scope) = &commit.scope {
+            let _ = write!(line, "**{}**: ", scope);
+        }
+        line.push_str(text);
+        if !commit.issues.is_empty() {
+            let links: Vec<_> = commit.issues.iter().map(|i| self.issue_link(i)).collect();
+            let _ = write!(line, " ({})", links.join(", "));
+        }
+        let _ = write!(line, " ({})", commit.short_hash());
+        line
+    }
+
+    /// `## [title] - date` followed by breaking changes, then one subsection per
+    /// commit type in config order, headed by the type's `desc`
+    pub fn section(
+        &self,
+        title: &str,
+        date: Option<&str>,
+        commits: &[ConventionalCommit],
+    ) -> String {
+        let mut out = match date {
+            Some(date) => format!("## [{}] - {}\n", title, date),
+            None => format!("## [{}]\n", title),
+        };
+
+        let breaking: Vec<_> = commits
+            .iter()
+            .filter_map(|c| c.breaking.as_deref().map(|note| (c, note)))
+            .map(|(c, note)| self.entry(c, if note.is_empty() { &c.subject } else { note }))
+            .collect();
+        if !breaking.is_empty() {
+            let _ = write!(
+                out,
+                "\n### {}\n\n{}\n",
+                tr().changelog_breaking,
+                breaking.join("\n")
+            );
+        }
+
+        for commit_type in &self.config.types {
+            let entries: Vec<_> = commits
+                .iter()
+                .filter(|c| c.commit_type.as_deref() == Some(commit_type.name.as_str()))
+                .map(|c| self.entry(c, &c.subject))
+                .collect();
+            if entries.is_empty() {
+                continue;
+            }
+            let heading = if commit_type.desc.is_empty() {
+                &commit_type.name
+            } else {
+                &commit_type.desc
+            };
+            let _ = write!(out, "\n### {}\n\n{}\n", heading, entries.join("\n"));
+        }
+
+        let other: Vec<_> = commits
+            .iter()
+            .filter(|c| {
+                !self
+                    .config
+                    .types
+                    .iter()
+                    .any(|t| c.commit_type.as_deref() == Some(t.name.as_str()))
+            })
+            .map(|c| self.entry(c, &c.subject))
+            .collect();
+        if !other.is_empty() {
+            let _ = write!(
+                out,
+                "\n### {}\n\n{}\n",
+                tr().changelog_other,
+                other.join("\n")
+            );
+        }
+        out
+    }
+}
+
+/// Insert `section` above the newest release in `existing`.
+///
+/// A leading section with the same heading is replaced, so running the command
+/// twice does not duplicate entries. A leading `[Unreleased]` section is replaced
+/// when `release` is set, as it covers the same commits, and kept on top otherwise.
+pub fn prepend(existing: Option<&str>, section: &str, release: bool) -> String {
+    let existing = existing.unwrap_or(CHANGELOG_HEADER);
+    let title = section_title(section);
+
+    let Some(mut start) = find_section(existing, 0) else {
+        return format!("{}\n\n{}", existing.trim_end(), section);
+    };
+    let next_section = |from: usize| find_section(existing, from + 1).unwrap_or(existing.len());
+    let first_title = section_title(&existing[start..]);
+    let mut end = start;
+    if first_title == title || (release && first_title == "## [Unreleased]") {
+        end = next_section(start);
+    } else if first_title == "## [Unreleased]" {
+        start = next_section(start);
+        end = start;
+    }
+
+    let mut out = existing[..start].to_string();
+    if !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+    out.push_str(section);
+    if end < existing.len() {
+        out.push('\n');
+        out.push_str(&existing[end..]);
+    }
+    out
+}
+
+/// `## [1.0.0] - 2025-01-01` → `## [1.0.0]`
+fn section_title(text: &str) -> &str {
+    let heading = text.lines().next().unwrap_or_default();
+    heading.split(" - ").next().unwrap_or(heading)
+}
+
+/// Write `section` into the configured changelog file, returning its path; see [`prepend`]
+pub fn write_section(config: &Config, section: &str, release: bool) -> anyhow::Result<PathBuf> {
+    let path = get_repo_root()?.join(&config.changelog.path);
+    let existing = std::fs::read_to_string(&path).ok();
+    std::fs::write(&path, prepend(existing.as_deref(), section, release))?;
+    Ok(path)
+}
+
+/// Byte offset of the first `## ` heading at or after `from`
+fn find_section(text: &str, from: usize) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if offset >= from && line.starts_with("## ") {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit_types::{CommitTypeConfig, EmojiConfig};
+
+    fn config() -> Config {
+        Config::new(
+            EmojiConfig::new(true),
+            vec![
+                CommitTypeConfig::new("feat".into(), "✨".into(), "Features".into()),
+                CommitTypeConfig::new("fix".into(), "🐛".into(), "Bug Fixes".into()),
+            ],
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    fn entry(hash: &str, subject: &str, body: &str) -> LogEntry {
+        LogEntry {
+            hash: hash.into(),
+            subject: subject.into(),
+            body: body.into(),
+        }
+    }
+
+    #[test]
+    fn test_parse_conventional_commit() {
+        let config = config();
+        let commit = ConventionalCommit::parse(
+            &entry(
+                "abc1234def",
+                "feat(#12)!: ✨ drop v1 config",
+                "BREAKING CHANGE: v1 is gone\n\nRefs PROJ-3",
+            ),
+            &config,
+        );
+        assert_eq!(commit.commit_type.as_deref(), Some("feat"));
+        assert_eq!(commit.subject, "drop v1 config");
+        assert_eq!(commit.scope, None);
+        assert_eq!(commit.issues, ["#12", "PROJ-3"]);
+        assert_eq!(commit.breaking.as_deref(), Some("v1 is gone"));
+
+        let plain = ConventionalCommit::parse(&entry("1", "Merge stuff", ""), &config);
+        assert_eq!(plain.commit_type, None);
+        assert_eq!(plain.subject, "Merge stuff");
+    }
+
+    #[test]
+    fn test_section_groups_by_type() {
+        let config = config();
+        let commits: Vec<_> = [
+            entry(
+                "aaaaaaa1",
+                "fix(parser): 🐛 handle empty input",
+                "Closes #7",
+            ),
+            entry("bbbbbbb2", "feat!: add v2 API", ""),
+            entry("ccccccc3", "update readme", ""),
+        ]
+        .iter()
+        .map(|e| ConventionalCommit::parse(e, &config))
+        .collect();
+        let writer = ChangelogWriter {
+            config: &config,
+            issue_url: Some("https://github.com/o/n/issues/{id}".into()),
+        };
+        let section = writer.section("1.2.0", Some("2025-01-02"), &commits);
+        assert_eq!(
+            section,
+            format!(
+                "## [1.2.0] - 2025-01-02
+
+### {}
+
+- add v2 API (bbbbbbb)
+
+### Features
+
+- add v2 API (bbbbbbb)
+
+### Bug Fixes
+
+- **parser**: handle empty input ([#7](https://github.com/o/n/issues/7)) (aaaaaaa)
+
+### {}
+
+- update readme (ccccccc)
+",
+                tr().changelog_breaking,
+                tr().changelog_other
+            )
+        );
+    }
+
+    #[test]
+    fn test_prepend_replaces_same_section() {
+        let first = prepend(None, "## [Unreleased]\n\n- a\n", false);
+        assert!(first.starts_with("# Changelog\n"));
+        assert!(first.ends_with("\n\n## [Unreleased]\n\n- a\n"));
+
+        let updated = prepend(Some(&first), "## [Unreleased]\n\n- a\n- b\n", false);
+        assert_eq!(updated.matches("## [Unreleased]").count(), 1);
+        assert!(updated.contains("- b"));
+
+        // An older tag goes below the unreleased changes
+        let older = prepend(Some(&updated), "## [0.9.0] - 2024-12-01\n\n- z\n", false);
+        assert!(older.find("## [Unreleased]").unwrap() < older.find("## [0.9.0]").unwrap());
+
+        let released = prepend(Some(&older), "## [1.0.0] - 2025-01-01\n\n- a\n- b\n", true);
+        assert!(!released.contains("[Unreleased]"));
+        assert!(released.find("## [1.0.0]").unwrap() < released.find("## [0.9.0]").unwrap());
+        assert!(released.contains("- z\n"));
+    }
+
+    #[test]
+    fn test_web_url() {
+        assert_eq!(
+            web_url("git@github.com:kitty-eu-org/aigcw.git").as_deref(),
+            Some("https://github.com/kitty-eu-org/aigcw")
+        );
+        assert_eq!(
+            web_url("ssh://git@gitlab.example.com:2222/group/app.git").as_deref(),
+            Some("https://gitlab.example.com/group/app")
+        );
+    }
+}
@@ -1,92 +1,365 @@
+use crate::app_config::default_true;
+use crate::git_utils::get_repo_root;
+use crate::i18n::{fill, tr};
 use derive_new::new;
-use std::path::Path;
+
+/// Built-in types as (name, emoji); descriptions come from the language catalog
+const BUILTIN_TYPES: &[(&str, &str)] = &[
+    ("feat", "✨"),
+    ("fix", "🐛"),
+    ("docs", "📚"),
+    ("style", "🎨"),
+    ("refactor", "♻️"),
+    ("perf", "⚡️"),
+    ("test", "✅"),
+    ("build", "📦️"),
+    ("ci", "👷"),
+    ("chore", "🔧"),
+    ("revert", "⏪️"),
+];
+
+#[allow(clippy::too_many_arguments)]
 #[derive(serde::Deserialize, new)]
 pub struct Config {
+    #[serde(default)]
     pub emoji: EmojiConfig,
+    #[serde(default = "builtin_types")]
     pub types: Vec<CommitTypeConfig>,
+    #[serde(default)]
+    pub privacy: PrivacyPolicy,
+    #[serde(default)]
+    pub issue: IssueConfig,
+    #[serde(default)]
+    pub breaking: BreakingConfig,
+    #[serde(default)]
+    pub changelog: ChangelogConfig,
+    #[serde(default)]
+    pub release: ReleaseConfig,
+    #[serde(default)]
+    pub branch: BranchConfig,
+    #[serde(default)]
+    pub review: ReviewConfig,
 }
 
 #[derive(serde::Deserialize, new)]
 pub struct EmojiConfig {
-    enable: bool,
+    pub enable: bool,
+}
+
+impl Default for EmojiConfig {
+    fn default() -> Self {
+        Self { enable: true }
+    }
 }
 
-#[derive(serde::Deserialize, Clone, new)]
+#[derive(serde::Deserialize, Clone, Debug, new)]
 pub struct CommitTypeConfig {
     pub name: String,  // 类型名称
     pub emoji: String, // 对应 emoji
     pub desc: String,  // 类型描述
 }
 
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub enum RemoteAction {
+    /// Abort instead of sending protected files to a remote provider
+    #[default]
+    Refuse,
+    /// Switch to the local provider described by `local_model` / `local_url`
+    UseLocal,
+}
+
+/// Repo-level rules for which paths may leave the machine
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct PrivacyPolicy {
+    /// Globs never included in any prompt
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Globs that may only be sent to a local provider such as Ollama
+    #[serde(default)]
+    pub local_only: Vec<String>,
+    #[serde(default)]
+    pub on_remote: RemoteAction,
+    pub local_model: Option<String>,
+    pub local_url: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IssueStyle {
+    /// `feat(#123): ...`, `#` is only added to numeric IDs
+    #[default]
+    Hash,
+    /// `feat(PROJ-123): ...`
+    Key,
+    /// No scope, `Closes #123` as a footer
+    Footer,
+}
+
+fn default_branch_pattern() -> String {
+    r"(?:^|/)([A-Z][A-Z0-9]*-\d+|\d+)(?:[-_]|$)".into()
+}
+
+fn default_footer_keyword() -> String {
+    "Closes".into()
+}
+
+/// How issue IDs are found in branch names and rendered in the message
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct IssueConfig {
+    /// Regex applied to the branch name, the first capture group is the issue ID
+    #[serde(default = "default_branch_pattern")]
+    pub branch_pattern: String,
+    #[serde(default)]
+    pub style: IssueStyle,
+    #[serde(default = "default_footer_keyword")]
+    pub footer_keyword: String,
+}
+
+impl Default for IssueConfig {
+    fn default() -> Self {
+        Self {
+            branch_pattern: default_branch_pattern(),
+            style: IssueStyle::default(),
+            footer_keyword: default_footer_keyword(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BreakingCheck {
+    /// Never suggest a breaking change
+    Off,
+    /// Flag removed `pub` items and changed function signatures in the diff
+    #[default]
+    Heuristic,
+    /// Ask the LLM, with the heuristic findings as hints
+    Llm,
+}
+
+/// How the wizard suggests `!` and a `BREAKING CHANGE:` footer
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct BreakingConfig {
+    #[serde(default)]
+    pub check: BreakingCheck,
+}
+
+fn default_changelog_path() -> String {
+    "CHANGELOG.md".into()
+}
+
+/// Where `gcw changelog` writes and how it links issues
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ChangelogConfig {
+    /// Relative to the repo root
+    #[serde(default = "default_changelog_path")]
+    pub path: String,
+    /// Issue link with an `{id}` placeholder; defaults to the `origin` remote's issues page
+    pub issue_url: Option<String>,
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        Self {
+            path: default_changelog_path(),
+            issue_url: None,
+        }
+    }
+}
+
+fn default_tag_prefix() -> String {
+    "v".into()
+}
+
+/// What `gcw release` touches besides the tag
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ReleaseConfig {
+    #[serde(default = "default_tag_prefix")]
+    pub tag_prefix: String,
+    /// Bump `version` in Cargo.toml, package.json and pyproject.toml
+    #[serde(default = "default_true")]
+    pub update_manifests: bool,
+    /// Prepend the release notes to the changelog file
+    #[serde(default = "default_true")]
+    pub changelog: bool,
+}
+
+impl Default for ReleaseConfig {
+    fn default() -> Self {
+        Self {
+            tag_prefix: default_tag_prefix(),
+            update_manifests: true,
+            changelog: true,
+        }
+    }
+}
+
+fn default_branch_name_pattern() -> String {
+    "{type}/{issue}-{slug}".into()
+}
+
+fn default_branch_max_length() -> usize {
+    50
+}
+
+/// How serious a `gcw review` finding is
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl TryFrom<String> for Severity {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        Severity::parse(&name).ok_or_else(|| format!("unknown severity: {}", name))
+    }
+}
+
+impl Severity {
+    /// Case-insensitive name, e.g. from `--block-on warning`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// `gcw review` settings
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct ReviewConfig {
+    /// Exit with an error (blocking the commit when run as a hook) on findings at or
+    /// above this severity; never blocks when unset
+    pub block_on: Option<Severity>,
+}
+
+/// Naming scheme for `gcw branch new` / `gcw branch suggest`
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct BranchConfig {
+    /// `{type}`, `{issue}` and `{slug}` are replaced; segments left empty are dropped
+    #[serde(default = "default_branch_name_pattern")]
+    pub pattern: String,
+    #[serde(default = "default_branch_max_length")]
+    pub max_length: usize,
+}
+
+impl Config {
+    /// Commands fall back to the first type, so at least one must be configured
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.types.is_empty() {
+            anyhow::bail!(tr().no_commit_types);
+        }
+        self.branch.validate()
+    }
+}
+
+impl BranchConfig {
+    /// Every branch name is built around the slug, so the pattern must place it
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.pattern.contains("{slug}") {
+            anyhow::bail!(fill(tr().branch_pattern_no_slug, &[&self.pattern]));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BranchConfig {
+    fn default() -> Self {
+        Self {
+            pattern: default_branch_name_pattern(),
+            max_length: default_branch_max_length(),
+        }
+    }
+}
+
 impl CommitTypeConfig {
     pub fn show_string(&self) -> String {
         format!("{}: {}", self.name, self.emoji)
     }
+
+    /// Entry shown in the type picker, including the description
+    pub fn menu_string(&self) -> String {
+        if self.desc.is_empty() {
+            self.show_string()
+        } else {
+            format!("{}  {}", self.show_string(), self.desc)
+        }
+    }
 }
 
+fn builtin_types() -> Vec<CommitTypeConfig> {
+    BUILTIN_TYPES
+        .iter()
+        .map(|(name, emoji)| {
+            CommitTypeConfig::new(
+                name.to_string(),
+                emoji.to_string(),
+                tr().type_desc(name).into(),
+            )
+        })
+        .collect()
+}
+
+/// `.commitconfig.toml` at the repository root, so it applies from any subdirectory
 pub fn load_config() -> anyhow::Result<Config> {
-    let commit_config_path = Path::new("example.txt");
+    let commit_config_path = get_repo_root()
+        .unwrap_or_default()
+        .join(".commitconfig.toml");
     if commit_config_path.exists() {
-        let content = std::fs::read_to_string(".commitconfig.toml")?;
-        Ok(toml::from_str(&content)?)
+        let content = std::fs::read_to_string(commit_config_path)?;
+        let config: Config = toml::from_str(&content)?;
+        config.validate()?;
+        Ok(config)
     } else {
-        let types = vec![
-            CommitTypeConfig {
-                name: "feat".into(),
-                emoji: "✨".into(),
-                desc: "新增功能".into(),
-            },
-            CommitTypeConfig {
-                name: "fix".into(),
-                emoji: "🐛".into(),
-                desc: "Bug修复".into(),
-            },
-            CommitTypeConfig {
-                name: "docs".into(),
-                emoji: "📚".into(),
-                desc: "文档更新".into(),
-            },
-            CommitTypeConfig {
-                name: "style".into(),
-                emoji: "🎨".into(),
-                desc: "代码样式调整".into(),
-            },
-            CommitTypeConfig {
-                name: "refactor".into(),
-                emoji: "♻️".into(),
-                desc: "代码重构".into(),
-            },
-            CommitTypeConfig {
-                name: "perf".into(),
-                emoji: "⚡️".into(),
-                desc: "性能优化".into(),
-            },
-            CommitTypeConfig {
-                name: "test".into(),
-                emoji: "✅".into(),
-                desc: "测试相关".into(),
-            },
-            CommitTypeConfig {
-                name: "build".into(),
-                emoji: "📦️".into(),
-                desc: "构建系统变更".into(),
-            },
-            CommitTypeConfig {
-                name: "ci".into(),
-                emoji: "👷".into(),
-                desc: "CI配置变更".into(),
-            },
-            CommitTypeConfig {
-                name: "chore".into(),
-                emoji: "🔧".into(),
-                desc: "其他杂项".into(),
-            },
-            CommitTypeConfig {
-                name: "revert".into(),
-                emoji: "⏪️".into(),
-                desc: "提交回滚".into(),
-            },
-        ];
-        Ok(Config::new(EmojiConfig::new(true), types))
+        Ok(Config::new(
+            EmojiConfig::default(),
+            builtin_types(),
+            PrivacyPolicy::default(),
+            IssueConfig::default(),
+            BreakingConfig::default(),
+            ChangelogConfig::default(),
+            ReleaseConfig::default(),
+            BranchConfig::default(),
+            ReviewConfig::default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_privacy_only_config_keeps_builtin_types() {
+        let config: Config = toml::from_str("[privacy]\nexclude = [\"secrets/**\"]\n").unwrap();
+        assert!(config.emoji.enable);
+        assert_eq!(config.types.len(), BUILTIN_TYPES.len());
+        assert_eq!(config.privacy.exclude, ["secrets/**"]);
+    }
+
+    #[test]
+    fn test_types_must_not_be_empty() {
+        let config: Config = toml::from_str("types = []\n").unwrap();
+        assert!(config.validate().is_err());
+        assert!(toml::from_str::<Config>("").unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_branch_pattern_needs_slug() {
+        let config: Config = toml::from_str("[branch]\npattern = \"{type}/{issue}\"\n").unwrap();
+        assert!(config.branch.validate().is_err());
+        assert!(BranchConfig::default().validate().is_ok());
     }
 }
@@ -0,0 +1,156 @@
+use crate::app_config::ContextConfig;
+use crate::git_utils::{get_current_branch, get_recent_subjects, get_repo_root};
+use crate::llm::estimate_tokens;
+use std::fmt::Write;
+
+/// Repo-provided notes describing domain terms, relative to the repo root
+pub const CONTEXT_FILE: &str = ".gcw/context.md";
+
+/// Optional project context added to the prompt next to the diff
+#[derive(Debug, Default, Clone)]
+pub struct PromptContext {
+    pub branch: Option<String>,
+    pub history: Vec<String>,
+    pub notes: Option<String>,
+    /// Issue entered for this commit and its title from the tracker
+    pub issue: Option<(String, String)>,
+}
+
+impl PromptContext {
+    /// Gather the enabled sources; a source that cannot be read is simply left out
+    pub fn collect(config: &ContextConfig) -> Self {
+        let branch = if config.branch {
+            get_current_branch().ok().flatten()
+        } else {
+            None
+        };
+        let history = if config.history > 0 {
+            get_recent_subjects(config.history).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let notes = if config.project_notes {
+            get_repo_root()
+                .ok()
+                .and_then(|root| std::fs::read_to_string(root.join(CONTEXT_FILE)).ok())
+                .map(|notes| notes.trim().to_string())
+                .filter(|notes| !notes.is_empty())
+        } else {
+            None
+        };
+        Self {
+            branch,
+            history,
+            notes,
+            issue: None,
+        }
+    }
+
+    pub fn history_text(&self) -> String {
+        self.history
+            .iter()
+            .map(|s| format!("- {}", s))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if let Some((id, title)) = &self.issue {
+            let _ = writeln!(out, "Related issue {}: {}\n", id, title);
+        }
+        if let Some(branch) = &self.branch {
+            let _ = writeln!(out, "Current branch: {}\n", branch);
+        }
+        if !self.history.is_empty() {
+            let _ = writeln!(
+                out,
+                "Recent commit subjects (match their style):\n{}\n",
+                self.history_text()
+            );
+        }
+        if let Some(notes) = &self.notes {
+            let _ = writeln!(out, "Project context:\n{}\n", notes);
+        }
+        out
+    }
+
+    pub fn tokens(&self) -> usize {
+        estimate_tokens(&self.render())
+    }
+
+    /// Shrink to at most `budget` tokens: notes are trimmed first, then the oldest
+    /// history, then the branch and issue
+    pub fn fit(&mut self, budget: usize) {
+        while self.tokens() > budget {
+            if let Some(notes) = &mut self.notes {
+                let keep = notes.len() / 2;
+                if keep < 64 {
+                    self.notes = None;
+                } else {
+                    let cut = (0..=keep)
+                        .rev()
+                        .find(|&i| notes.is_char_boundary(i))
+                        .unwrap_or(0);
+                    notes.truncate(cut);
+                    notes.push_str("\n...");
+                }
+            } else if self.history.pop().is_none()
+                && self.branch.take().is_none()
+                && self.issue.take().is_none()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Split `max_tokens` between context and diff; returns the diff's share (0 = unlimited).
+///
+/// Context may use at most a quarter of the budget so the diff always dominates.
+pub fn allocate_budget(context: &mut PromptContext, max_tokens: usize) -> usize {
+    if max_tokens == 0 {
+        return 0;
+    }
+    context.fit(max_tokens / 4);
+    max_tokens.saturating_sub(context.tokens()).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> PromptContext {
+        PromptContext {
+            branch: Some("feature/PROJ-1-retry".into()),
+            history: (0..20).map(|i| format!("fix: handle case {}", i)).collect(),
+            notes: Some("Tenant: a customer organisation. ".repeat(100)),
+            issue: Some(("PROJ-1".into(), "Retry failed uploads".into())),
+        }
+    }
+
+    #[test]
+    fn test_render_sections() {
+        let rendered = context().render();
+        assert!(rendered.starts_with("Related issue PROJ-1: Retry failed uploads"));
+        assert!(rendered.contains("Current branch: feature/PROJ-1-retry"));
+        assert!(rendered.contains("- fix: handle case 0"));
+        assert!(rendered.contains("Project context:\nTenant"));
+    }
+
+    #[test]
+    fn test_allocate_budget_trims_context() {
+        let mut ctx = context();
+        let diff_budget = allocate_budget(&mut ctx, 400);
+        assert!(ctx.tokens() <= 100);
+        assert_eq!(diff_budget, 400 - ctx.tokens());
+        assert!(ctx.branch.is_some());
+    }
+
+    #[test]
+    fn test_unlimited_budget_keeps_everything() {
+        let mut ctx = context();
+        assert_eq!(allocate_budget(&mut ctx, 0), 0);
+        assert_eq!(ctx.history.len(), 20);
+    }
+}
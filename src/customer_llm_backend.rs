@@ -1,14 +1,23 @@
-use reqwest::Client;
-use rllm::chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, Tool};
+use reqwest::{Certificate, Client, Method, Proxy, RequestBuilder};
+use rllm::builder::LLMBackend;
+use rllm::chat::{ChatMessage, ChatProvider, ChatResponse, Tool, Usage};
 use rllm::completion::{CompletionProvider, CompletionResponse};
 use rllm::embedding::EmbeddingProvider;
 use rllm::error::LLMError;
-use rllm::models::ModelsProvider;
+use rllm::models::{
+    ModelListRequest, ModelListResponse, ModelsProvider, StandardModelEntry,
+    StandardModelListResponse, StandardModelListResponseInner,
+};
 use rllm::stt::SpeechToTextProvider;
 use rllm::tts::TextToSpeechProvider;
 use rllm::{async_trait, LLMProvider, ToolCall};
-use serde::{Deserialize, Serialize};
 
+use crate::app_config::WireFormat;
+use crate::wire::{ChatReply, ChatRequest};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct AIGCWLLM {
     pub url: String,
     pub api_key: Option<String>,
@@ -20,27 +29,19 @@ pub struct AIGCWLLM {
     pub stream: Option<bool>,
     pub top_p: Option<f32>,
     pub top_k: Option<u32>,
+    /// Request and response schema, also deciding the endpoint path and key header
+    pub wire_format: WireFormat,
+    /// Use `url` as the chat endpoint instead of completing it, see [`endpoint_url`]
+    pub url_verbatim: bool,
+    /// Sent with every request, e.g. `api-key` for Azure
+    pub headers: HashMap<String, String>,
+    /// Added to every request URL, e.g. `api-version`
+    pub query: HashMap<String, String>,
     client: Client,
 }
 
-#[derive(Serialize)]
-struct AIGCWLLMChatRequest<'a> {
-    model: String,
-    messages: Vec<AIGCWLLMChatMessage<'a>>,
-    // stream: bool,
-    // temperature: Option<f32>,
-}
-
-#[derive(Serialize, Debug)]
-struct AIGCWLLMChatMessage<'a> {
-    role: &'a str,
-    content: &'a str,
-}
-
-#[derive(Deserialize, Debug, Default)]
-pub struct AIGCWLLMChatResponse {
-    choices: Vec<AIGCWLLMChatChoice>,
-}
+#[derive(Debug)]
+pub struct AIGCWLLMChatResponse(ChatReply);
 
 impl std::fmt::Display for AIGCWLLMChatResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -48,32 +49,28 @@ impl std::fmt::Display for AIGCWLLMChatResponse {
     }
 }
 
-#[derive(Deserialize, Debug)]
-struct AIGCWLLMChatChoice {
-    message: AIGCWLLMChatMsg,
-}
-
-#[derive(Deserialize, Debug)]
-struct AIGCWLLMChatMsg {
-    content: String,
-}
 impl ChatResponse for AIGCWLLMChatResponse {
     fn text(&self) -> Option<String> {
-        self.choices.first().and_then(|c| {
-            if c.message.content.is_empty() {
-                None
-            } else {
-                Some(c.message.content.clone())
-            }
-        })
+        Some(self.0.text.clone()).filter(|text| !text.is_empty())
     }
 
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
         None
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.0.usage.map(|usage| Usage {
+            prompt_tokens: usage.prompt,
+            completion_tokens: usage.completion,
+            total_tokens: usage.prompt + usage.completion,
+            completion_tokens_details: None,
+            prompt_tokens_details: None,
+        })
+    }
 }
 
 impl AIGCWLLM {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         url: String,
         api_key: impl Into<String>,
@@ -99,9 +96,117 @@ impl AIGCWLLM {
             stream,
             top_p: None,
             top_k: None,
+            headers: HashMap::new(),
+            query: HashMap::new(),
+            wire_format: WireFormat::default(),
+            url_verbatim: false,
             client: builder.build().expect("Failed to build reqwest Client"),
         }
     }
+
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.wire_format = wire_format;
+        self
+    }
+
+    pub fn with_url_verbatim(mut self, url_verbatim: bool) -> Self {
+        self.url_verbatim = url_verbatim;
+        self
+    }
+
+    pub fn with_headers(
+        mut self,
+        headers: HashMap<String, String>,
+        query: HashMap<String, String>,
+    ) -> Self {
+        self.headers = headers;
+        self.query = query;
+        self
+    }
+
+    /// Rebuild the client to go through `proxy` and trust the certificates in `ca_bundle`
+    pub fn with_transport(
+        mut self,
+        proxy: Option<&str>,
+        ca_bundle: Option<&Path>,
+    ) -> Result<Self, LLMError> {
+        let mut builder = Client::builder();
+        if let Some(sec) = self.timeout_seconds {
+            builder = builder.timeout(std::time::Duration::from_secs(sec));
+        }
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        if let Some(path) = ca_bundle {
+            let pem = std::fs::read(path).map_err(|e| {
+                LLMError::InvalidRequest(format!("Cannot read {}: {}", path.display(), e))
+            })?;
+            for cert in Certificate::from_pem_bundle(&pem)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        self.client = builder.build()?;
+        Ok(self)
+    }
+
+    fn endpoint(&self) -> String {
+        if self.url_verbatim {
+            return self.url.trim().to_string();
+        }
+        self.wire_format.endpoint(&self.url, &self.model)
+    }
+
+    /// A request to `url` with the key, extra headers and query parameters
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self.client.request(method, url).query(&self.query);
+        if let Some(api_key) = self.api_key.as_ref().filter(|key| !key.is_empty()) {
+            request = match self.wire_format.auth_header() {
+                (_, true) => request.bearer_auth(api_key),
+                (name, false) => request.header(name, api_key),
+            };
+        }
+        for (name, value) in self.wire_format.fixed_headers() {
+            request = request.header(*name, *value);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+/// `url` with the endpoint `path` appended when it is an API base such as
+/// `https://host/v1` or `https://host/openai/deployments/x`; a bare host also gets
+/// `version`. Any other URL is already the endpoint and is used as written.
+pub fn endpoint_url(url: &str, version: &str, path: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    if url.ends_with(path) {
+        return url.to_string();
+    }
+    let url_path = url
+        .split("://")
+        .nth(1)
+        .unwrap_or(url)
+        .split_once('/')
+        .map_or("", |(_, path)| path);
+    if url_path.is_empty() {
+        format!("{}{}{}", url, version, path)
+    } else if is_api_base(url_path) {
+        format!("{}{}", url, path)
+    } else {
+        url.to_string()
+    }
+}
+
+/// A URL path ending in a version segment (`v1`, `v1beta`) or naming an Azure
+/// OpenAI deployment
+fn is_api_base(path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').collect();
+    let versioned = segments.last().is_some_and(|last| {
+        last.strip_prefix('v')
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+    });
+    versioned || matches!(segments.as_slice(), [.., "openai", "deployments", _])
 }
 
 #[async_trait]
@@ -110,50 +215,35 @@ impl ChatProvider for AIGCWLLM {
         &self,
         messages: &[rllm::chat::ChatMessage],
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
-        let mut messages: Vec<AIGCWLLMChatMessage> = messages
-            .iter()
-            .map(|m| AIGCWLLMChatMessage {
-                role: match m.role {
-                    ChatRole::User => "user",
-                    ChatRole::Assistant => "assistant",
-                },
-                content: &m.content,
+        let body = self
+            .wire_format
+            .encode(&ChatRequest {
+                model: &self.model,
+                system: self.system.as_deref(),
+                messages,
+                max_tokens: self.max_tokens,
+                temperature: self.temperature,
+                top_p: self.top_p,
+                top_k: self.top_k,
+                stream: self.stream,
             })
-            .collect();
-
-        if let Some(system) = &self.system {
-            messages.insert(
-                0,
-                AIGCWLLMChatMessage {
-                    role: "system",
-                    content: system,
-                },
-            );
-        }
-
-        let body = AIGCWLLMChatRequest {
-            model: self.model.clone(),
-            messages,
-            // temperature: self.temperature,
-            // stream: self.stream.unwrap_or(false),
-        };
+            .map_err(|e| LLMError::JsonError(e.to_string()))?;
 
         let mut request = self
-            .client
-            .post(self.url.as_str())
+            .request(Method::POST, &self.endpoint())
             .header("Content-Type", "application/json")
             .json(&body);
-        if let Some(api_key) = &self.api_key {
-            request = request.bearer_auth(&api_key);
-        }
 
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
         let resp = request.send().await?.error_for_status()?;
-        let json_resp: AIGCWLLMChatResponse = resp.json().await?;
-        Ok(Box::new(json_resp))
+        let reply = self
+            .wire_format
+            .decode(&resp.text().await?)
+            .map_err(|e| LLMError::JsonError(e.to_string()))?;
+        Ok(Box::new(AIGCWLLMChatResponse(reply)))
     }
 
     async fn chat_with_tools(
@@ -187,7 +277,35 @@ impl EmbeddingProvider for AIGCWLLM {
 }
 
 #[async_trait]
-impl ModelsProvider for AIGCWLLM {}
+impl ModelsProvider for AIGCWLLM {
+    async fn list_models(
+        &self,
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        let url = self.wire_format.models_url(&self.endpoint());
+        let resp = self
+            .request(Method::GET, &url)
+            .send()
+            .await?
+            .error_for_status()?;
+        let ids = self
+            .wire_format
+            .decode_models(&resp.text().await?)
+            .map_err(|e| LLMError::JsonError(e.to_string()))?;
+        let data = ids
+            .into_iter()
+            .map(|id| StandardModelEntry {
+                id,
+                created: None,
+                extra: serde_json::Value::Null,
+            })
+            .collect();
+        Ok(Box::new(StandardModelListResponse {
+            inner: StandardModelListResponseInner { data },
+            backend: LLMBackend::OpenAI,
+        }))
+    }
+}
 
 #[async_trait]
 impl TextToSpeechProvider for AIGCWLLM {}
@@ -202,3 +320,56 @@ impl SpeechToTextProvider for AIGCWLLM {
 }
 
 impl LLMProvider for AIGCWLLM {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_url() {
+        for (url, expected) in [
+            (
+                "https://api.example.com",
+                "https://api.example.com/v1/chat/completions",
+            ),
+            (
+                "https://api.example.com/",
+                "https://api.example.com/v1/chat/completions",
+            ),
+            (
+                "https://api.example.com/v1",
+                "https://api.example.com/v1/chat/completions",
+            ),
+            (
+                "http://localhost:8000/v1/chat/completions/",
+                "http://localhost:8000/v1/chat/completions",
+            ),
+            (
+                "https://res.openai.azure.com/openai/deployments/gpt-4o",
+                "https://res.openai.azure.com/openai/deployments/gpt-4o/chat/completions",
+            ),
+            (
+                "https://gateway.corp/api/v3",
+                "https://gateway.corp/api/v3/chat/completions",
+            ),
+            (
+                "https://gateway.corp/llm/invoke",
+                "https://gateway.corp/llm/invoke",
+            ),
+        ] {
+            assert_eq!(endpoint_url(url, "/v1", "/chat/completions"), expected);
+        }
+    }
+
+    #[test]
+    fn test_url_verbatim() {
+        let url = "https://gateway.corp/v1";
+        let llm = |verbatim| {
+            AIGCWLLM::new(url.into(), "", None, None, None, None, None, None)
+                .with_url_verbatim(verbatim)
+                .endpoint()
+        };
+        assert_eq!(llm(false), "https://gateway.corp/v1/chat/completions");
+        assert_eq!(llm(true), url);
+    }
+}
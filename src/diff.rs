@@ -0,0 +1,428 @@
+use crate::app_config::DiffConfig;
+use crate::llm::estimate_tokens;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed { from: String },
+    Copied { from: String },
+}
+
+impl FileStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Modified => "modified",
+            FileStatus::Renamed { .. } => "renamed",
+            FileStatus::Copied { .. } => "copied",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` line, including any trailing function context
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    pub status: FileStatus,
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl FileDiff {
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            status: FileStatus::Modified,
+            binary: false,
+            hunks: Vec::new(),
+            insertions: 0,
+            deletions: 0,
+        }
+    }
+
+    /// The path before a rename or copy
+    pub fn old_path(&self) -> Option<&str> {
+        match &self.status {
+            FileStatus::Renamed { from } | FileStatus::Copied { from } => Some(from),
+            _ => None,
+        }
+    }
+
+    /// One-line description used when the content itself is not sent
+    pub fn summary_line(&self) -> String {
+        let target = match &self.status {
+            FileStatus::Renamed { from } | FileStatus::Copied { from } => {
+                format!("{} -> {}", from, self.path)
+            }
+            _ => self.path.clone(),
+        };
+        if self.binary {
+            format!("{} ({}, binary)", target, self.status.label())
+        } else {
+            format!(
+                "{} ({}, +{} -{})",
+                target,
+                self.status.label(),
+                self.insertions,
+                self.deletions
+            )
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        let old_path = self.old_path().unwrap_or(&self.path);
+        let _ = writeln!(out, "diff --git a/{} b/{}", old_path, self.path);
+        match &self.status {
+            FileStatus::Modified => {}
+            FileStatus::Renamed { from } => {
+                let _ = writeln!(out, "rename from {}\nrename to {}", from, self.path);
+            }
+            FileStatus::Copied { from } => {
+                let _ = writeln!(out, "copy from {}\ncopy to {}", from, self.path);
+            }
+            status => {
+                let _ = writeln!(out, "{} file", status.label());
+            }
+        }
+        for hunk in &self.hunks {
+            out.push_str(&hunk.header);
+            out.push('\n');
+            for line in &hunk.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Typed view of `git diff --staged`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StagedDiff {
+    pub files: Vec<FileDiff>,
+}
+
+impl StagedDiff {
+    pub fn parse(raw: &str) -> Self {
+        let mut files: Vec<FileDiff> = Vec::new();
+
+        for line in raw.lines() {
+            if let Some(rest) = line.strip_prefix("diff --git ") {
+                files.push(FileDiff::new(path_from_diff_header(rest)));
+                continue;
+            }
+            let Some(file) = files.last_mut() else {
+                continue;
+            };
+
+            if !line.starts_with("@@") {
+                if let Some(hunk) = file.hunks.last_mut() {
+                    if line.starts_with('+') {
+                        file.insertions += 1;
+                    } else if line.starts_with('-') {
+                        file.deletions += 1;
+                    }
+                    hunk.lines.push(line.to_string());
+                    continue;
+                }
+            }
+
+            if line.starts_with("@@") {
+                file.hunks.push(Hunk {
+                    header: line.to_string(),
+                    lines: Vec::new(),
+                });
+            } else if line.starts_with("new file mode") {
+                file.status = FileStatus::Added;
+            } else if line.starts_with("deleted file mode") {
+                file.status = FileStatus::Deleted;
+            } else if let Some(from) = line.strip_prefix("rename from ") {
+                file.status = FileStatus::Renamed {
+                    from: unquote(from),
+                };
+            } else if let Some(to) = line.strip_prefix("rename to ") {
+                file.path = unquote(to);
+            } else if let Some(from) = line.strip_prefix("copy from ") {
+                file.status = FileStatus::Copied {
+                    from: unquote(from),
+                };
+            } else if let Some(to) = line.strip_prefix("copy to ") {
+                file.path = unquote(to);
+            } else if let Some(path) = line
+                .strip_prefix("--- ")
+                .and_then(|p| side_path(p, "a/"))
+                .or_else(|| line.strip_prefix("+++ ").and_then(|p| side_path(p, "b/")))
+            {
+                // `+++ b/<path>` comes last, so a deleted file keeps its `--- a/<path>`
+                file.path = path;
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                file.binary = true;
+            }
+        }
+
+        Self { files }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn insertions(&self) -> usize {
+        self.files.iter().map(|f| f.insertions).sum()
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.files.iter().map(|f| f.deletions).sum()
+    }
+
+    /// Render the diff as prompt text.
+    ///
+    /// Binary files and files matching `config.ignore` are reduced to a single
+    /// note, and once `budget` tokens are spent (usually `config.max_tokens` minus
+    /// other prompt context, 0 = unlimited) the remaining files are only listed by
+    /// name and stats.
+    pub fn render(&self, config: &DiffConfig, budget: usize) -> anyhow::Result<String> {
+        let ignore = build_globset(&config.ignore)?;
+        let mut out = String::new();
+        let mut notes = Vec::new();
+        let mut used = 0;
+
+        for file in &self.files {
+            if file.binary {
+                notes.push(format!("binary file {}", file.summary_line()));
+                continue;
+            }
+            if ignore.is_match(&file.path) {
+                notes.push(format!(
+                    "{}: {}",
+                    ignored_note(&file.path),
+                    file.summary_line()
+                ));
+                continue;
+            }
+
+            let mut section = String::new();
+            file.render(&mut section);
+            let cost = estimate_tokens(&section);
+            if budget > 0 && used + cost > budget {
+                notes.push(format!("omitted to fit budget: {}", file.summary_line()));
+                continue;
+            }
+            used += cost;
+            out.push_str(&section);
+        }
+
+        for note in notes {
+            let _ = writeln!(out, "# {}", note);
+        }
+        Ok(out)
+    }
+}
+
+fn ignored_note(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if name.ends_with(".lock") || name.contains("-lock.") || name.ends_with(".sum") {
+        "updated lockfile"
+    } else {
+        "updated generated file"
+    }
+}
+
+/// The path in a `diff --git a/<path> b/<path>` header, for files without `---`/`+++`
+/// lines such as binary files. Both sides name the same path, so the header is split in
+/// the middle rather than at a ` b/` that may be part of the path.
+pub(crate) fn path_from_diff_header(rest: &str) -> String {
+    let mid = rest.len() / 2;
+    if let (Some(a), Some(b)) = (rest.get(..mid), rest.get(mid + 1..)) {
+        if let Some(path) = side_path(a, "a/").filter(|p| side_path(b, "b/").as_ref() == Some(p)) {
+            return path;
+        }
+    }
+    match rest.rfind(" b/") {
+        Some(idx) => rest[idx + 3..].to_string(),
+        None => rest.to_string(),
+    }
+}
+
+/// A `---`/`+++` or header path without its `a/` or `b/` side prefix
+fn side_path(path: &str, prefix: &str) -> Option<String> {
+    unquote(path).strip_prefix(prefix).map(str::to_string)
+}
+
+/// A path as git prints it; paths with special characters are quoted with C-style escapes
+fn unquote(path: &str) -> String {
+    let Some(inner) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c = match (c, chars.next_if(|_| c == '\\')) {
+            (c, None) => c,
+            (_, Some('n')) => '\n',
+            (_, Some('t')) => '\t',
+            (_, Some(digit @ '0'..='7')) => {
+                // Octal escapes are the UTF-8 bytes of non-ASCII characters
+                let mut byte = digit as u8 - b'0';
+                while let Some(digit) = chars.next_if(|d| ('0'..='7').contains(d)) {
+                    byte = byte.wrapping_mul(8).wrapping_add(digit as u8 - b'0');
+                }
+                bytes.push(byte);
+                continue;
+            }
+            (_, Some(escaped)) => escaped,
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+pub fn build_globset(patterns: &[String]) -> anyhow::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+        // `Cargo.lock` or `*.min.js` should also match in subdirectories
+        if !pattern.contains('/') {
+            builder.add(Glob::new(&format!("**/{}", pattern))?);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@ fn main() {
+ fn a() {}
+-fn b() {}
++fn b(x: u32) {}
++fn c() {}
+diff --git a/Cargo.lock b/Cargo.lock
+index 3333333..4444444 100644
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -10,1 +10,1 @@
+-version = \"1.0.0\"
++version = \"1.0.1\"
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..5555555
+Binary files /dev/null and b/logo.png differ
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index 6666666..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+
+    #[test]
+    fn test_parse_statuses_and_stats() {
+        let diff = StagedDiff::parse(SAMPLE);
+        assert_eq!(diff.files.len(), 5);
+
+        let lib = &diff.files[0];
+        assert_eq!(lib.path, "src/lib.rs");
+        assert_eq!(lib.status, FileStatus::Modified);
+        assert_eq!((lib.insertions, lib.deletions), (2, 1));
+        assert_eq!(lib.hunks.len(), 1);
+        assert_eq!(lib.hunks[0].lines.len(), 4);
+
+        assert!(diff.files[2].binary);
+        assert_eq!(diff.files[2].status, FileStatus::Added);
+        assert_eq!(
+            diff.files[3].status,
+            FileStatus::Renamed {
+                from: "old.rs".into()
+            }
+        );
+        assert_eq!(diff.files[3].path, "new.rs");
+        assert_eq!(diff.files[4].path, "gone.txt");
+        assert_eq!(diff.files[4].status, FileStatus::Deleted);
+        assert_eq!((diff.insertions(), diff.deletions()), (3, 3));
+    }
+
+    #[test]
+    fn test_render_filters_ignored_and_binary() {
+        let diff = StagedDiff::parse(SAMPLE);
+        let config = DiffConfig {
+            ignore: vec!["Cargo.lock".into()],
+            max_tokens: 0,
+        };
+        let rendered = diff.render(&config, config.max_tokens).unwrap();
+        assert!(rendered.contains("+fn c() {}"));
+        assert!(!rendered.contains("1.0.1"));
+        assert!(rendered.contains("# updated lockfile: Cargo.lock (modified, +1 -1)"));
+        assert!(rendered.contains("# binary file logo.png (added, binary)"));
+        assert!(rendered.contains("rename from old.rs"));
+    }
+
+    #[test]
+    fn test_render_respects_budget() {
+        let diff = StagedDiff::parse(SAMPLE);
+        let config = DiffConfig {
+            ignore: vec![],
+            max_tokens: 1,
+        };
+        let rendered = diff.render(&config, config.max_tokens).unwrap();
+        assert!(!rendered.contains("fn c()"));
+        assert!(rendered.contains("# omitted to fit budget: src/lib.rs (modified, +2 -1)"));
+    }
+
+    #[test]
+    fn test_parse_paths_with_spaces_and_quotes() {
+        let diff = StagedDiff::parse(
+            r#"diff --git a/docs/a b/c.png b/docs/a b/c.png
+index 1111111..2222222 100644
+Binary files a/docs/a b/c.png and b/docs/a b/c.png differ
+diff --git "a/docs/caf\303\251 notes.md" "b/docs/caf\303\251 notes.md"
+index 3333333..4444444 100644
+--- "a/docs/caf\303\251 notes.md"
++++ "b/docs/caf\303\251 notes.md"
+@@ -1 +1 @@
+-old
++new
+diff --git a/x b/y.txt b/x b/y.txt
+deleted file mode 100644
+index 5555555..0000000
+--- a/x b/y.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+"#,
+        );
+        let paths: Vec<&str> = diff.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["docs/a b/c.png", "docs/café notes.md", "x b/y.txt"]);
+    }
+
+    #[test]
+    fn test_globset_matches_nested_paths() {
+        let set = build_globset(&["*.min.js".into(), "dist/**".into()]).unwrap();
+        assert!(set.is_match("web/app.min.js"));
+        assert!(set.is_match("dist/bundle.js"));
+        assert!(!set.is_match("src/dist.rs"));
+    }
+}
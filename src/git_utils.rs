@@ -1,9 +1,30 @@
+use crate::diff::StagedDiff;
+use crate::i18n::tr;
 use anyhow::Context;
+use std::path::PathBuf;
 use std::process::Command;
 
+/// Flags that keep user diff settings (color, external drivers, `diff.noprefix`,
+/// `diff.mnemonicPrefix`) from changing the output [`StagedDiff::parse`] reads
+const DIFF_FORMAT: [&str; 4] = [
+    "--no-color",
+    "--no-ext-diff",
+    "--src-prefix=a/",
+    "--dst-prefix=b/",
+];
+
+/// `git <command>` with [`DIFF_FORMAT`] and `args`, parsed
+fn git_diff(command: &str, args: &[&str]) -> anyhow::Result<StagedDiff> {
+    let mut full = vec![command];
+    full.extend(DIFF_FORMAT);
+    full.extend(args);
+    Ok(StagedDiff::parse(&git_output(&full)?))
+}
+
 pub fn get_diff_content() -> anyhow::Result<String> {
     let diff_output = Command::new("git")
         .args(["diff", "--staged"])
+        .args(DIFF_FORMAT)
         .output()
         .context("Failed to execute git diff --staged")?;
 
@@ -13,7 +34,131 @@ pub fn get_diff_content() -> anyhow::Result<String> {
 
     let diff_content = String::from_utf8_lossy(&diff_output.stdout);
     if diff_content.trim().is_empty() {
-        anyhow::bail!("No staged changes detected.");
+        anyhow::bail!(tr().no_staged_changes);
     }
     Ok(diff_content.to_string())
 }
+
+pub fn get_staged_diff() -> anyhow::Result<StagedDiff> {
+    Ok(StagedDiff::parse(&get_diff_content()?))
+}
+
+/// Staged changes like [`get_staged_diff`], but empty instead of an error when
+/// nothing is staged
+pub fn get_index_diff() -> anyhow::Result<StagedDiff> {
+    git_diff("diff", &["--staged"])
+}
+
+/// Unstaged changes in the working tree
+pub fn get_worktree_diff() -> anyhow::Result<StagedDiff> {
+    git_diff("diff", &[])
+}
+
+/// Changes on HEAD since it diverged from `base` (`git diff base...HEAD`)
+pub fn get_range_diff(base: &str) -> anyhow::Result<StagedDiff> {
+    git_diff("diff", &[&format!("{}...HEAD", base)])
+}
+
+/// Changes between two revisions (`git diff from to`)
+pub fn get_diff_between(from: &str, to: &str) -> anyhow::Result<StagedDiff> {
+    git_diff("diff", &[from, to])
+}
+
+/// Changes introduced by a single commit, against its first parent
+pub fn get_commit_diff(rev: &str) -> anyhow::Result<StagedDiff> {
+    git_diff(
+        "show",
+        &[
+            "--format=",
+            "-M",
+            // Diff a merge against its first parent instead of the combined `diff --cc`
+            "-m",
+            "--first-parent",
+            rev,
+        ],
+    )
+}
+
+/// Branch pull requests usually target: `origin/HEAD`, else `main` or `master`
+pub fn default_base_branch() -> anyhow::Result<String> {
+    if let Ok(head) = git_output(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"]) {
+        return Ok(head);
+    }
+    for branch in ["main", "master"] {
+        if git_output(&["rev-parse", "--verify", "--quiet", branch]).is_ok() {
+            return Ok(branch.to_string());
+        }
+    }
+    anyhow::bail!(tr().no_base_branch)
+}
+
+/// Run git and return its trimmed stdout, failing on a non-zero exit
+pub fn git_output(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to execute git {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Current branch name, `None` on a detached HEAD
+pub fn get_current_branch() -> anyhow::Result<Option<String>> {
+    let branch = git_output(&["branch", "--show-current"])?;
+    Ok((!branch.is_empty()).then_some(branch))
+}
+
+/// Subjects of the last `count` commits, newest first
+pub fn get_recent_subjects(count: usize) -> anyhow::Result<Vec<String>> {
+    let subjects = git_output(&["log", &format!("-{}", count), "--format=%s"])?;
+    Ok(subjects.lines().map(str::to_string).collect())
+}
+
+pub fn get_repo_root() -> anyhow::Result<PathBuf> {
+    Ok(PathBuf::from(git_output(&[
+        "rev-parse",
+        "--show-toplevel",
+    ])?))
+}
+
+/// One commit from `git log`
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub hash: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Non-merge commits in `range` (e.g. `v1.0.0..HEAD`), newest first
+pub fn get_log(range: &str) -> anyhow::Result<Vec<LogEntry>> {
+    let log = git_output(&["log", "--no-merges", "--format=%H%x1f%s%x1f%b%x1e", range])?;
+    Ok(log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(3, '\x1f');
+            Some(LogEntry {
+                hash: fields.next().filter(|h| !h.is_empty())?.to_string(),
+                subject: fields.next()?.to_string(),
+                body: fields.next().unwrap_or_default().trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Most recent tag starting with `prefix` reachable from `rev`, `None` when there is none
+pub fn get_last_tag(rev: &str, prefix: &str) -> Option<String> {
+    let pattern = format!("{}*", prefix);
+    git_output(&["describe", "--tags", "--abbrev=0", "--match", &pattern, rev]).ok()
+}
+
+/// Remote URL of `origin`, if configured
+pub fn get_origin_url() -> Option<String> {
+    git_output(&["remote", "get-url", "origin"]).ok()
+}
@@ -0,0 +1,167 @@
+use crate::git_utils::git_output;
+use crate::i18n::{fill, tr};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Todo list for `git rebase -i` that keeps every commit in `commits` (oldest first)
+/// and sets the message of those in `messages` from the file it maps to
+pub fn rebase_todo(commits: &[String], messages: &HashMap<String, PathBuf>) -> String {
+    let mut todo = String::new();
+    for commit in commits {
+        todo.push_str(&format!("pick {}\n", commit));
+        if let Some(path) = messages.get(commit) {
+            todo.push_str(&format!(
+                "exec git commit --amend --only --quiet --allow-empty -F {}\n",
+                shell_quote(&path.to_string_lossy())
+            ));
+        }
+    }
+    todo
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Replace the messages of `rewrites` (`(hash, message)`, commits on the current
+/// branch, oldest first) without changing their content.
+///
+/// HEAD alone is amended in place, anything older goes through a scripted
+/// `git rebase -i` that is aborted again if a step fails.
+pub fn reword_commits(rewrites: &[(String, String)]) -> anyhow::Result<()> {
+    let Some((oldest, _)) = rewrites.first() else {
+        return Ok(());
+    };
+    let head = git_output(&["rev-parse", "HEAD"])?;
+    if let [(hash, message)] = rewrites {
+        if *hash == head {
+            git_output(&[
+                "commit",
+                "--amend",
+                "--only",
+                "--quiet",
+                "--allow-empty",
+                "-m",
+                message,
+            ])?;
+            return Ok(());
+        }
+    }
+
+    for (hash, _) in rewrites {
+        if git_output(&["merge-base", "--is-ancestor", hash, "HEAD"]).is_err() {
+            anyhow::bail!(fill(tr().not_on_branch, &[hash]));
+        }
+    }
+    let base = git_output(&["rev-parse", "--verify", "--quiet", &format!("{}^", oldest)]).ok();
+    let range = match &base {
+        Some(base) => format!("{}..HEAD", base),
+        None => "HEAD".to_string(),
+    };
+    if !git_output(&["rev-list", "--merges", &range])?.is_empty() {
+        anyhow::bail!(tr().reword_merges);
+    }
+    let commits: Vec<String> = git_output(&["rev-list", "--reverse", &range])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+
+    let dir = std::env::temp_dir().join(format!("gcw-reword-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let result = run_rebase(&dir, base.as_deref(), &commits, rewrites);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn run_rebase(
+    dir: &Path,
+    base: Option<&str>,
+    commits: &[String],
+    rewrites: &[(String, String)],
+) -> anyhow::Result<()> {
+    let mut messages = HashMap::new();
+    for (i, (hash, message)) in rewrites.iter().enumerate() {
+        let path = dir.join(format!("message-{}.txt", i));
+        std::fs::write(&path, message)?;
+        messages.insert(hash.clone(), path);
+    }
+    let todo = dir.join("todo");
+    std::fs::write(&todo, rebase_todo(commits, &messages))?;
+
+    let mut command = Command::new("git");
+    command
+        .args(["rebase", "--interactive", "--autostash", "--quiet"])
+        .env(
+            "GIT_SEQUENCE_EDITOR",
+            format!("cp {}", shell_quote(&todo.to_string_lossy())),
+        )
+        .env("GIT_EDITOR", "true");
+    match base {
+        Some(base) => command.arg(base),
+        None => command.arg("--root"),
+    };
+    let output = command.output()?;
+    if !output.status.success() {
+        let _ = git_output(&["rebase", "--abort"]);
+        anyhow::bail!(fill(
+            tr().rebase_failed,
+            &[&String::from_utf8_lossy(&output.stderr).trim()]
+        ));
+    }
+    Ok(())
+}
+
+/// Replace the commits after `base` with one commit of the same content.
+///
+/// Refuses when changes are staged, since they would end up in the commit. HEAD is
+/// moved back if the commit fails, e.g. when a hook rejects it.
+pub fn squash_onto(base: &str, message: &str) -> anyhow::Result<()> {
+    if git_output(&["diff", "--cached", "--quiet"]).is_err() {
+        anyhow::bail!(tr().squash_staged);
+    }
+    if git_output(&["merge-base", "--is-ancestor", base, "HEAD"]).is_err() {
+        anyhow::bail!(fill(tr().not_ancestor, &[&base]));
+    }
+    let head = git_output(&["rev-parse", "HEAD"])?;
+    git_output(&["reset", "--quiet", "--soft", base])?;
+    if let Err(err) = git_output(&["commit", "--quiet", "-m", message]) {
+        git_output(&["reset", "--quiet", "--soft", &head])?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Run `git merge --squash` with `args` and leave `message` in `SQUASH_MSG`, where
+/// `git commit` picks it up. `false` when the merge stopped on conflicts.
+pub fn merge_squash(args: &[String], message: &str) -> anyhow::Result<bool> {
+    let output = Command::new("git")
+        .args(["merge", "--squash"])
+        .args(args)
+        .output()?;
+    let conflicts = !git_output(&["diff", "--name-only", "--diff-filter=U"])?.is_empty();
+    if !output.status.success() && !conflicts {
+        anyhow::bail!(fill(
+            tr().merge_squash_failed,
+            &[&String::from_utf8_lossy(&output.stderr).trim()]
+        ));
+    }
+    let squash_msg = git_output(&["rev-parse", "--git-path", "SQUASH_MSG"])?;
+    std::fs::write(squash_msg, message)?;
+    Ok(!conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_todo() {
+        let commits = vec!["aaa".to_string(), "bbb".to_string(), "ccc".to_string()];
+        let messages = HashMap::from([("bbb".to_string(), PathBuf::from("/tmp/it's.txt"))]);
+        assert_eq!(
+            rebase_todo(&commits, &messages),
+            "pick aaa\npick bbb\nexec git commit --amend --only --quiet --allow-empty -F '/tmp/it'\\''s.txt'\npick ccc\n"
+        );
+    }
+}
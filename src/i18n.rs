@@ -0,0 +1,432 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Defaults to English, the language of gcw's UI and generated messages before the
+/// setting existed
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "zh-CN")]
+    SimplifiedChinese,
+}
+
+impl Language {
+    /// Name used when instructing the LLM which language to write in
+    pub fn prompt_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::SimplifiedChinese => "Simplified Chinese (简体中文)",
+        }
+    }
+}
+
+/// UI strings for one language; `{}` placeholders are filled with [`fill`]
+pub struct Catalog {
+    pub select_commit_type: &'static str,
+    pub issue_prompt: &'static str,
+    pub issue_lookup_failed: &'static str,
+    pub tracker_no_base_url: &'static str,
+    pub tracker_no_path: &'static str,
+    pub remote_url_unparsable: &'static str,
+    pub no_changes: &'static str,
+    pub no_staged_changes: &'static str,
+    pub generating: &'static str,
+    pub no_terminal: &'static str,
+    pub llm_disabled: &'static str,
+    pub llm_empty_reply: &'static str,
+    pub unknown_type: &'static str,
+    pub breaking_prompt: &'static str,
+    pub breaking_desc_prompt: &'static str,
+    pub breaking_detected: &'static str,
+    pub changelog_breaking: &'static str,
+    pub changelog_other: &'static str,
+    pub changelog_written: &'static str,
+    pub no_commits: &'static str,
+    pub nothing_to_release: &'static str,
+    pub generating_pr: &'static str,
+    pub no_base_branch: &'static str,
+    pub branch_name_prompt: &'static str,
+    pub split_plan: &'static str,
+    pub split_confirm: &'static str,
+    pub split_rollback: &'static str,
+    pub split_flags: &'static str,
+    pub reword_prompt: &'static str,
+    pub reword_choices: &'static [&'static str],
+    pub reword_subject_prompt: &'static str,
+    pub commit_no_terminal: &'static str,
+    pub reword_no_terminal: &'static str,
+    pub reword_done: &'static str,
+    pub not_on_branch: &'static str,
+    pub reword_merges: &'static str,
+    pub rebase_failed: &'static str,
+    pub squash_usage: &'static str,
+    pub squash_confirm: &'static str,
+    pub squash_no_terminal: &'static str,
+    pub no_commit_types: &'static str,
+    pub squash_conflicts: &'static str,
+    pub squash_done: &'static str,
+    pub squash_staged: &'static str,
+    pub not_ancestor: &'static str,
+    pub merge_squash_failed: &'static str,
+    pub reviewing: &'static str,
+    pub explaining: &'static str,
+    pub cache_cleared: &'static str,
+    pub usage_empty: &'static str,
+    pub ollama_unreachable: &'static str,
+    pub ollama_pull_prompt: &'static str,
+    pub ollama_missing_model: &'static str,
+    pub ollama_no_models: &'static str,
+    pub unknown_provider: &'static str,
+    pub no_models: &'static str,
+    pub fetching_models: &'static str,
+    pub models_failed: &'static str,
+    pub init_no_terminal: &'static str,
+    pub init_provider: &'static str,
+    pub init_url: &'static str,
+    pub init_wire_format: &'static str,
+    pub init_api_key: &'static str,
+    pub init_api_key_keep: &'static str,
+    pub init_model: &'static str,
+    pub init_model_other: &'static str,
+    pub init_model_input: &'static str,
+    pub config_saved: &'static str,
+    pub unknown_usage_group: &'static str,
+    pub usage_total: &'static str,
+    pub usage_partial: &'static str,
+    pub usage_columns: &'static [&'static str],
+    pub review_clean: &'static str,
+    pub review_summary: &'static str,
+    pub review_blocked: &'static str,
+    pub review_skipped: &'static str,
+    pub unknown_severity: &'static str,
+    pub hook_installed: &'static str,
+    pub hook_exists: &'static str,
+    pub branch_empty: &'static str,
+    pub branch_pattern_no_slug: &'static str,
+    pub file_written: &'static str,
+    pub release_plan: &'static str,
+    pub release_tagged: &'static str,
+    pub release_dirty: &'static str,
+    pub masked_secrets: &'static str,
+    pub refuse_secrets: &'static str,
+    pub excluded_files: &'static str,
+    pub refuse_remote: &'static str,
+    pub switch_local: &'static str,
+    pub local_url_remote: &'static str,
+    pub config_upgrade: &'static str,
+    pub create_config_dir_failed: &'static str,
+    pub write_config_failed: &'static str,
+    /// Descriptions of the built-in commit types, by type name
+    pub type_descs: &'static [(&'static str, &'static str)],
+}
+
+impl Catalog {
+    pub fn type_desc(&self, name: &str) -> &'static str {
+        self.type_descs
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, desc)| *desc)
+            .unwrap_or_default()
+    }
+}
+
+static EN: Catalog = Catalog {
+    select_commit_type: "Select commit type",
+    issue_prompt: "Issue number (optional, press Enter to skip)",
+    issue_lookup_failed: "Issue lookup failed for {}: {}",
+    tracker_no_base_url: "tracker.base_url is required for Jira",
+    tracker_no_path: "tracker.path is required for Local",
+    remote_url_unparsable: "Cannot parse remote URL: {}",
+    no_changes: "No changes to commit.",
+    no_staged_changes: "No staged changes detected.",
+    generating: "Generating message for {} file(s) (+{} -{})...",
+    no_terminal: "No terminal to prompt on, pass --type <name>",
+    llm_disabled: "LLM generation is disabled, set llm_config.enable = true",
+    llm_empty_reply: "The LLM returned an empty reply",
+    unknown_type: "Unknown commit type: {}",
+    breaking_prompt: "Is this a breaking change?",
+    breaking_desc_prompt: "Describe the breaking change (optional)",
+    breaking_detected: "Possible breaking change: {}",
+    changelog_breaking: "Breaking Changes",
+    changelog_other: "Other Changes",
+    changelog_written: "Updated {}",
+    no_commits: "No commits found in {}",
+    branch_name_prompt: "Branch name",
+    split_plan: "Split into {} commit(s):",
+    split_confirm: "Create these commits?",
+    split_rollback: "Commit failed, the original staged changes were restored",
+    split_flags: "--split cannot be combined with --all, --patch or --amend",
+    reword_prompt: "Use the new message?",
+    reword_choices: &["Accept", "Edit", "Keep the old message"],
+    reword_subject_prompt: "Subject line",
+    commit_no_terminal:
+        "No terminal to pick the commit type on, pass a message or use gcw msg --type <name>",
+    reword_no_terminal: "No terminal to review the new messages on, use --dry-run to preview them",
+    reword_done: "Reworded {} commit(s)",
+    not_on_branch: "{} is not on the current branch",
+    reword_merges: "Cannot reword across merge commits, rebase them away first",
+    rebase_failed: "git rebase failed, the branch was left as it was: {}",
+    squash_usage: "Usage: gcw squash <base> (e.g. HEAD~5) or gcw merge --squash <branch>",
+    squash_confirm: "Commit with this message?",
+    squash_no_terminal: "No terminal to confirm the message on, use --dry-run to preview it",
+    no_commit_types: "No commit types are configured in .commitconfig.toml",
+    squash_conflicts: "Resolve the conflicts and run git commit, the generated message is prepared",
+    squash_done: "Squashed {} commit(s)",
+    squash_staged: "Unstage your changes first, they would be included in the squashed commit",
+    not_ancestor: "{} is not an ancestor of HEAD",
+    merge_squash_failed: "git merge --squash failed: {}",
+    reviewing: "Reviewing {} file(s) (+{} -{})...",
+    explaining: "Explaining {} commit(s) in {} file(s) (+{} -{})...",
+    cache_cleared: "Removed {} cached response(s)",
+    usage_empty: "No LLM usage recorded yet",
+    ollama_unreachable: "Cannot reach Ollama at {}, is `ollama serve` running?",
+    ollama_pull_prompt: "Model {} is not installed in Ollama. Pull it now?",
+    ollama_missing_model: "Model {} is not installed in Ollama, run `ollama pull {}`",
+    ollama_no_models: "No models installed in Ollama at {}, try `ollama pull {}`",
+    unknown_provider: "Unknown provider: {}",
+    no_models: "The provider did not list any models",
+    fetching_models: "Fetching the available models...",
+    models_failed: "Could not list the models: {}",
+    init_no_terminal: "No terminal to prompt on, edit the config file instead",
+    init_provider: "LLM provider",
+    init_url: "Endpoint URL",
+    init_wire_format: "API format of the endpoint",
+    init_api_key: "API key",
+    init_api_key_keep: "API key (leave empty to use {})",
+    init_model: "Model",
+    init_model_other: "Other...",
+    init_model_input: "Model ID",
+    config_saved: "Saved {}",
+    unknown_usage_group: "Unknown grouping: {} (expected day, model or repo)",
+    usage_total: "total",
+    usage_partial:
+        "Costs marked + leave out {} call(s) of models without a price in [usage.prices]",
+    usage_columns: &[
+        "day",
+        "model",
+        "repo",
+        "calls",
+        "prompt",
+        "completion",
+        "cost",
+    ],
+    review_clean: "No findings.",
+    review_summary: "{} finding(s)",
+    review_skipped: "Review skipped, the commit goes ahead: {}",
+    review_blocked:
+        "{} finding(s) at or above {}, commit blocked (skip with git commit --no-verify)",
+    unknown_severity: "Unknown severity: {} (expected info, warning or error)",
+    hook_installed: "Installed the review hook in {}",
+    hook_exists: "{} already exists, add `gcw review` to it yourself",
+    branch_empty: "Cannot derive a branch name from \"{}\"",
+    branch_pattern_no_slug: "branch.pattern \"{}\" in .commitconfig.toml has no {slug} placeholder",
+    generating_pr: "Describing {} commit(s) in {} file(s) (+{} -{})...",
+    no_base_branch: "Cannot find the base branch, pass --base <branch>",
+    file_written: "Wrote {}",
+    nothing_to_release: "No feat, fix or breaking commits since {}, nothing to release",
+    release_plan: "Releasing {} -> {} ({} bump)",
+    release_tagged: "Tagged {}, publish it with: git push --follow-tags",
+    release_dirty: "{} has uncommitted changes, commit or stash them before releasing",
+    masked_secrets: "Masked possible secrets: {}",
+    refuse_secrets: "Refusing to send staged changes to the LLM, possible secrets detected: {}",
+    excluded_files: "Excluded {} protected file(s) from the prompt.",
+    refuse_remote: "Refusing to send local-only files to remote provider {}: {}",
+    switch_local: "Local-only files staged ({}), switching to local provider.",
+    local_url_remote:
+        "privacy.local_url {} is not on this machine, refusing to send local-only files to it",
+    config_upgrade: "Found config file v{}, upgrading to v{}...",
+    create_config_dir_failed: "Failed to create config directory: {}",
+    write_config_failed: "Failed to write config file: {}",
+    type_descs: &[
+        ("feat", "A new feature"),
+        ("fix", "A bug fix"),
+        ("docs", "Documentation only changes"),
+        ("style", "Code style changes"),
+        ("refactor", "Code refactoring"),
+        ("perf", "Performance improvements"),
+        ("test", "Adding or updating tests"),
+        ("build", "Build system changes"),
+        ("ci", "CI configuration changes"),
+        ("chore", "Other changes"),
+        ("revert", "Revert a previous commit"),
+    ],
+};
+
+static ZH_CN: Catalog = Catalog {
+    select_commit_type: "选择提交类型",
+    issue_prompt: "Issue 编号（可选，回车跳过）",
+    issue_lookup_failed: "查询 Issue {} 失败：{}",
+    tracker_no_base_url: "Jira 需要配置 tracker.base_url",
+    tracker_no_path: "Local 需要配置 tracker.path",
+    remote_url_unparsable: "无法解析远程仓库地址：{}",
+    no_changes: "没有需要提交的变更。",
+    no_staged_changes: "未检测到已暂存的变更。",
+    generating: "正在为 {} 个文件生成提交信息 (+{} -{})...",
+    no_terminal: "没有可交互的终端，请使用 --type <name>",
+    llm_disabled: "LLM 生成未启用，请设置 llm_config.enable = true",
+    llm_empty_reply: "LLM 返回了空回复",
+    unknown_type: "未知的提交类型: {}",
+    breaking_prompt: "这是破坏性变更吗？",
+    breaking_desc_prompt: "描述破坏性变更（可选）",
+    breaking_detected: "可能存在破坏性变更: {}",
+    changelog_breaking: "破坏性变更",
+    changelog_other: "其他变更",
+    changelog_written: "已更新 {}",
+    no_commits: "{} 中没有提交",
+    branch_name_prompt: "分支名称",
+    split_plan: "拆分为 {} 个提交:",
+    split_confirm: "创建这些提交吗？",
+    split_rollback: "提交失败，已恢复原来的暂存变更",
+    split_flags: "--split 不能与 --all、--patch 或 --amend 同时使用",
+    reword_prompt: "使用新的提交信息吗？",
+    reword_choices: &["接受", "编辑", "保留原信息"],
+    reword_subject_prompt: "标题行",
+    commit_no_terminal:
+        "没有可交互的终端来选择提交类型，请传入提交信息或使用 gcw msg --type <name>",
+    reword_no_terminal: "没有可交互的终端来确认新的提交信息，请使用 --dry-run 预览",
+    reword_done: "已改写 {} 个提交",
+    not_on_branch: "{} 不在当前分支上",
+    reword_merges: "无法跨合并提交修改提交信息，请先通过 rebase 去掉合并提交",
+    rebase_failed: "git rebase 失败，分支保持原样：{}",
+    squash_usage: "用法: gcw squash <base>（例如 HEAD~5）或 gcw merge --squash <branch>",
+    squash_confirm: "使用此信息提交吗？",
+    squash_no_terminal: "没有可交互的终端来确认提交信息，请使用 --dry-run 预览",
+    no_commit_types: ".commitconfig.toml 中没有配置提交类型",
+    squash_conflicts: "请解决冲突后运行 git commit，生成的提交信息已准备好",
+    squash_done: "已合并 {} 个提交",
+    squash_staged: "请先取消暂存的变更，否则它们会被包含在压缩后的提交中",
+    not_ancestor: "{} 不是 HEAD 的祖先提交",
+    merge_squash_failed: "git merge --squash 失败：{}",
+    reviewing: "正在审查 {} 个文件 (+{} -{})...",
+    explaining: "正在解释 {} 个提交、{} 个文件 (+{} -{})...",
+    cache_cleared: "已删除 {} 条缓存的响应",
+    usage_empty: "尚未记录任何 LLM 用量",
+    ollama_unreachable: "无法连接 {} 上的 Ollama，`ollama serve` 是否已运行？",
+    ollama_pull_prompt: "Ollama 中未安装模型 {}，现在拉取吗？",
+    ollama_missing_model: "Ollama 中未安装模型 {}，请运行 `ollama pull {}`",
+    ollama_no_models: "{} 上的 Ollama 没有安装任何模型，试试 `ollama pull {}`",
+    unknown_provider: "未知的提供方：{}",
+    no_models: "提供方没有列出任何模型",
+    fetching_models: "正在获取可用模型...",
+    models_failed: "无法列出模型：{}",
+    init_no_terminal: "没有可交互的终端，请直接编辑配置文件",
+    init_provider: "LLM 提供方",
+    init_url: "接口地址",
+    init_wire_format: "接口的 API 格式",
+    init_api_key: "API 密钥",
+    init_api_key_keep: "API 密钥（留空则使用 {}）",
+    init_model: "模型",
+    init_model_other: "其他...",
+    init_model_input: "模型 ID",
+    config_saved: "已保存 {}",
+    unknown_usage_group: "未知的分组方式：{}（应为 day、model 或 repo）",
+    usage_total: "合计",
+    usage_partial: "标有 + 的费用未包含 {} 次未在 [usage.prices] 中定价的模型调用",
+    usage_columns: &["日期", "模型", "仓库", "调用", "输入", "输出", "费用"],
+    review_clean: "没有发现问题。",
+    review_summary: "发现 {} 个问题",
+    review_skipped: "已跳过审查，继续提交: {}",
+    review_blocked: "有 {} 个问题达到或超过 {}，已阻止提交（可用 git commit --no-verify 跳过）",
+    unknown_severity: "未知的严重级别: {}（应为 info、warning 或 error）",
+    hook_installed: "已在 {} 安装审查钩子",
+    hook_exists: "{} 已存在，请自行在其中加入 `gcw review`",
+    branch_empty: "无法从 \"{}\" 生成分支名称",
+    branch_pattern_no_slug: ".commitconfig.toml 中的 branch.pattern \"{}\" 缺少 {slug} 占位符",
+    generating_pr: "正在为 {} 个提交、{} 个文件生成描述 (+{} -{})...",
+    no_base_branch: "找不到基准分支，请使用 --base <branch>",
+    file_written: "已写入 {}",
+    nothing_to_release: "自 {} 以来没有 feat、fix 或破坏性提交，无需发布",
+    release_plan: "发布 {} -> {} ({} 升级)",
+    release_tagged: "已创建标签 {}，使用 git push --follow-tags 推送",
+    release_dirty: "{} 有未提交的修改，请先提交或 stash 后再发布",
+    masked_secrets: "已屏蔽疑似敏感信息: {}",
+    refuse_secrets: "检测到疑似敏感信息，拒绝将暂存变更发送给 LLM: {}",
+    excluded_files: "已从提示词中排除 {} 个受保护文件。",
+    refuse_remote: "拒绝将仅限本地的文件发送给远程服务 {}: {}",
+    switch_local: "已暂存仅限本地的文件 ({})，切换到本地模型。",
+    local_url_remote: "privacy.local_url {} 不在本机，拒绝向其发送仅限本地的文件",
+    config_upgrade: "发现旧版配置文件 (v{})，正在升级到 v{}...",
+    create_config_dir_failed: "无法创建配置目录: {}",
+    write_config_failed: "无法写入配置文件: {}",
+    type_descs: &[
+        ("feat", "新增功能"),
+        ("fix", "Bug修复"),
+        ("docs", "文档更新"),
+        ("style", "代码样式调整"),
+        ("refactor", "代码重构"),
+        ("perf", "性能优化"),
+        ("test", "测试相关"),
+        ("build", "构建系统变更"),
+        ("ci", "CI配置变更"),
+        ("chore", "其他杂项"),
+        ("revert", "提交回滚"),
+    ],
+};
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// Select the UI language once; called by `load_app_config`
+pub fn set_language(language: Language) {
+    let _ = LANGUAGE.set(language);
+}
+
+pub fn catalog(language: Language) -> &'static Catalog {
+    match language {
+        Language::English => &EN,
+        Language::SimplifiedChinese => &ZH_CN,
+    }
+}
+
+/// Catalog for the configured language, the default one until [`set_language`] is called
+pub fn tr() -> &'static Catalog {
+    catalog(LANGUAGE.get().copied().unwrap_or_default())
+}
+
+/// Replace each `{}` in `template` with the next argument
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        assert_eq!(
+            fill(EN.generating, &[&3, &10, &2]),
+            "Generating message for 3 file(s) (+10 -2)..."
+        );
+    }
+
+    #[test]
+    fn test_catalogs_cover_same_types() {
+        let names = |c: &Catalog| c.type_descs.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+        assert_eq!(names(&EN), names(&ZH_CN));
+        assert_eq!(
+            catalog(Language::SimplifiedChinese).type_desc("feat"),
+            "新增功能"
+        );
+    }
+
+    #[test]
+    fn test_language_config_names() {
+        let lang: Language = serde_json::from_str("\"zh-CN\"").unwrap();
+        assert_eq!(lang, Language::SimplifiedChinese);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::commit_types::IssueConfig;
+use crate::git_utils::get_current_branch;
+use regex::Regex;
+
+/// Issue ID in `branch` according to `pattern`'s first capture group
+pub fn issue_from_branch(branch: &str, pattern: &str) -> anyhow::Result<Option<String>> {
+    let regex = Regex::new(pattern)?;
+    Ok(regex
+        .captures(branch)
+        .and_then(|caps| caps.get(1).or_else(|| caps.get(0)))
+        .map(|m| m.as_str().to_string()))
+}
+
+/// Issue ID for the current branch, if any
+pub fn detect_issue(config: &IssueConfig) -> anyhow::Result<Option<String>> {
+    match get_current_branch()? {
+        Some(branch) => issue_from_branch(&branch, &config.branch_pattern),
+        None => Ok(None),
+    }
+}
+
+/// `123` / `#123` → `#123`, `PROJ-123` stays as is
+pub fn issue_reference(issue: &str) -> String {
+    let id = issue.trim().trim_start_matches('#');
+    if id.chars().all(|c| c.is_ascii_digit()) {
+        format!("#{}", id)
+    } else {
+        id.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(branch: &str) -> Option<String> {
+        issue_from_branch(branch, &IssueConfig::default().branch_pattern).unwrap()
+    }
+
+    #[test]
+    fn test_issue_from_branch_defaults() {
+        assert_eq!(detect("feature/PROJ-123-foo").as_deref(), Some("PROJ-123"));
+        assert_eq!(detect("fix/456").as_deref(), Some("456"));
+        assert_eq!(detect("456-crash-on-start").as_deref(), Some("456"));
+        assert_eq!(detect("main"), None);
+        assert_eq!(detect("release/1.2"), None);
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let issue = issue_from_branch("gh-77-login", r"gh-(\d+)").unwrap();
+        assert_eq!(issue.as_deref(), Some("77"));
+    }
+
+    #[test]
+    fn test_issue_reference() {
+        assert_eq!(issue_reference("#123"), "#123");
+        assert_eq!(issue_reference("123"), "#123");
+        assert_eq!(issue_reference("PROJ-1"), "PROJ-1");
+    }
+}
@@ -1,82 +1,482 @@
-use crate::app_config::{LLMConfig, LLMProvider};
+use crate::app_config::{AppConfig, LLMConfig, LLMProvider};
+use crate::cache::ResponseCache;
+use crate::commit_types::CommitTypeConfig;
+use crate::context::PromptContext;
 use crate::customer_llm_backend::AIGCWLLM;
+use crate::i18n::tr;
+use crate::ollama;
+use crate::prompt::{PromptTemplate, RenderedPrompt};
+use crate::redact::prepare_for_llm;
+use crate::review::{parse_findings, Finding};
+use crate::split::{parse_plan, SplitGroup};
+use crate::usage;
 use rllm::builder::{LLMBackend, LLMBuilder};
 use rllm::chat::{ChatMessage, ChatRole, MessageType};
+use std::collections::HashMap;
 
-impl From<&LLMProvider> for LLMBackend {
-    fn from(provider: &LLMProvider) -> Self {
-        match provider {
+impl TryFrom<&LLMProvider> for LLMBackend {
+    type Error = anyhow::Error;
+
+    fn try_from(provider: &LLMProvider) -> anyhow::Result<Self> {
+        Ok(match provider {
             LLMProvider::OpenAI => LLMBackend::OpenAI,
             LLMProvider::Anthropic => LLMBackend::Anthropic,
             LLMProvider::Ollama => LLMBackend::Ollama,
             LLMProvider::DeepSeek => LLMBackend::DeepSeek,
             LLMProvider::XAI => LLMBackend::XAI,
             LLMProvider::Phind => LLMBackend::Phind,
-            _ => panic!("Invalid LLMProvider"),
-        }
+            LLMProvider::Google => LLMBackend::Google,
+            _ => anyhow::bail!("{:?} is not supported yet", provider),
+        })
     }
 }
-pub async fn generate_msg(
-    commit_type_str: &str,
-    git_diff_content: &str,
+
+/// Cheap token estimate (~4 bytes per token) used for prompt budgeting
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Build the chat client for `llm_config`, with `system` as the system prompt
+pub fn build_llm(
     llm_config: &LLMConfig,
-) -> anyhow::Result<String> {
-    if !llm_config.enable {
-        return Ok("".into());
-    }
-    let llm = if llm_config.is_custom() {
-        let llm = AIGCWLLM::new(
-            llm_config.url.clone().unwrap(),
-            llm_config.api_key.clone().unwrap(),
-            llm_config.model.clone(),
-            None,
-            None,
-            Some(60),
-            None,
-            None,
-        );
-        Box::new(llm)
+    system: &str,
+) -> anyhow::Result<Box<dyn rllm::LLMProvider>> {
+    let llm: Box<dyn rllm::LLMProvider> = if llm_config.is_custom() {
+        Box::new(
+            AIGCWLLM::new(
+                llm_config
+                    .url
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("The CUSTOM provider needs a url"))?,
+                llm_config.api_key().unwrap_or_default(),
+                llm_config.model.clone(),
+                None,
+                None,
+                Some(60),
+                Some(system.to_string()),
+                None,
+            )
+            .with_wire_format(llm_config.wire_format)
+            .with_url_verbatim(llm_config.url_verbatim)
+            .with_headers(llm_config.headers.clone(), llm_config.query.clone())
+            .with_transport(llm_config.proxy.as_deref(), llm_config.ca_bundle.as_deref())?,
+        )
     } else {
-        LLMBuilder::new()
-            .backend(LLMBackend::from(&llm_config.provider)) // or LLMBackend::Anthropic, LLMBackend::Ollama, LLMBackend::DeepSeek, LLMBackend::XAI, LLMBackend::Phind ...
-            .api_key(llm_config.api_key.clone().unwrap())
-            .model(llm_config.model.clone().unwrap()) // or model("claude-3-5-sonnet-20240620") or model("grok-2-latest") or model("deepseek-chat") or model("llama3.1") or model("Phind-70B") ...
-            .build()
-            .expect("Failed to build LLM")
+        // or LLMBackend::Anthropic, LLMBackend::Ollama, LLMBackend::DeepSeek, LLMBackend::XAI, LLMBackend::Phind ...
+        let mut builder = LLMBuilder::new()
+            .backend(LLMBackend::try_from(&llm_config.provider)?)
+            .system(system);
+        // local providers such as Ollama need neither a key nor, with a default, a model
+        if let Some(api_key) = &llm_config.api_key() {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(url) = &llm_config.url {
+            builder = builder.base_url(url);
+        }
+        if let Some(model) = &llm_config.model {
+            builder = builder.model(model); // or model("claude-3-5-sonnet-20240620") or model("grok-2-latest") or model("deepseek-chat") or model("llama3.1") or model("Phind-70B") ...
+        }
+        builder.build()?
     };
+    Ok(llm)
+}
 
-    let prompt = format!(
-        r#"Generate a concise git commit message based on the selected commit type and diff. Requirements:
-
-1. Commit type [{}] defines the message's intent, but should NOT appear in output
-2. Start with a strong action verb aligned with the type's purpose (add/fix/improve/etc)
-3. Focus on user-facing value specific to the commit type
-4. Maximum 12 words, no technical details/paths/code
-
-Type-verb mapping guidance:
-• feat: add, introduce, implement, enable
-• fix: resolve, prevent, avoid, repair
-• perf: optimize, reduce, accelerate, speed up
-• docs: document, describe, clarify
-• test: verify, validate, check
-
-Examples (type in brackets for reference only):
-[feat] → Add quick filters to report dashboard
-[fix] → Retain form data after network errors
-[perf] → Reduce PDF generation memory usage
-[docs] → Clarify multi-factor auth setup steps
-
-Diff to analyze:
-{}"#,
-        commit_type_str
-        ,git_diff_content
-    );
+/// A model `gcw models` and `gcw config init` can offer
+#[derive(Debug, Clone)]
+pub struct ModelInfo {
+    pub name: String,
+    /// Bytes on disk, known for Ollama only
+    pub size: Option<u64>,
+}
+
+/// Installed models for Ollama, the provider's `/models` list otherwise
+pub async fn available_models(llm_config: &LLMConfig) -> anyhow::Result<Vec<ModelInfo>> {
+    if llm_config.provider == LLMProvider::Ollama {
+        let base = ollama::base_url(llm_config.url.as_deref());
+        return Ok(ollama::list_models(&base)
+            .await?
+            .into_iter()
+            .map(|model| ModelInfo {
+                name: model.name,
+                size: Some(model.size),
+            })
+            .collect());
+    }
+    let llm = build_llm(llm_config, "")?;
+    let mut names = llm.list_models(None).await?.get_models();
+    names.sort();
+    Ok(names
+        .into_iter()
+        .map(|name| ModelInfo { name, size: None })
+        .collect())
+}
+
+/// Send a rendered prompt and return the reply text
+pub async fn ask(llm_config: &LLMConfig, prompt: &RenderedPrompt) -> anyhow::Result<String> {
+    let prepared;
+    let llm_config = if llm_config.provider == LLMProvider::Ollama {
+        prepared = ollama::prepare(llm_config).await?;
+        &prepared
+    } else {
+        llm_config
+    };
+    let llm = build_llm(llm_config, &prompt.system)?;
     let messages = vec![ChatMessage {
         role: ChatRole::User,
         message_type: MessageType::default(),
-        content: prompt,
+        content: prompt.user.clone(),
     }];
 
     let chat_resp = llm.chat(&messages).await?;
-    Ok(chat_resp.text().expect("call llm error"))
+    // e.g. a content-filtered response or a Gemini candidate without parts
+    let reply = chat_resp.text().unwrap_or_default();
+    let (prompt_tokens, completion_tokens, estimated) = match chat_resp.usage() {
+        Some(usage) => (
+            usage.prompt_tokens as usize,
+            usage.completion_tokens as usize,
+            false,
+        ),
+        None => (
+            estimate_tokens(&prompt.system) + estimate_tokens(&prompt.user),
+            estimate_tokens(&reply),
+            true,
+        ),
+    };
+    // the log is informational, never fail a command over it
+    let _ = usage::record(
+        llm_config,
+        prompt_tokens as u64,
+        completion_tokens as u64,
+        estimated,
+    );
+    if reply.trim().is_empty() {
+        anyhow::bail!(tr().llm_empty_reply);
+    }
+    Ok(reply)
+}
+
+/// Render the commit message prompt from the configured template, redacted and ready to send
+pub fn commit_prompt(
+    commit_type: &CommitTypeConfig,
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<RenderedPrompt> {
+    let vars = HashMap::from([
+        ("type", commit_type.name.clone()),
+        ("type_desc", commit_type.desc.clone()),
+        ("emoji", commit_type.emoji.clone()),
+        ("language", app_config.language.prompt_name().to_string()),
+        ("branch", context.branch.clone().unwrap_or_default()),
+        ("history", context.history_text()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        (
+            "issue",
+            context
+                .issue
+                .as_ref()
+                .map(|(id, _)| id.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "issue_title",
+            context
+                .issue
+                .as_ref()
+                .map(|(_, t)| t.clone())
+                .unwrap_or_default(),
+        ),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("commit")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    Ok(prompt)
+}
+
+/// Ask the LLM whether `git_diff_content` breaks users; `Some(description)` if it does
+pub async fn breaking_change(
+    git_diff_content: &str,
+    findings: &[String],
+    app_config: &AppConfig,
+) -> anyhow::Result<Option<String>> {
+    let vars = HashMap::from([
+        ("language", app_config.language.prompt_name().to_string()),
+        (
+            "findings",
+            findings
+                .iter()
+                .map(|f| format!("- {}", f))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("breaking")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    let reply = ask(&app_config.llm_config, &prompt).await?;
+    let reply = reply.trim();
+    if reply.is_empty() || reply.to_uppercase().starts_with("NONE") {
+        Ok(None)
+    } else {
+        Ok(Some(reply.to_string()))
+    }
+}
+
+/// Pull request title and Markdown body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrDescription {
+    pub title: String,
+    pub body: String,
+}
+
+impl PrDescription {
+    /// Split a reply into the title line and the body after it
+    pub fn parse(reply: &str) -> Self {
+        let reply = reply.trim();
+        let (title, body) = reply.split_once('\n').unwrap_or((reply, ""));
+        let title = title.trim().trim_start_matches('#').trim();
+        let title = title.strip_prefix("Title:").unwrap_or(title).trim();
+        Self {
+            title: title.trim_matches(|c| c == '"' || c == '`').to_string(),
+            body: body.trim().to_string(),
+        }
+    }
+}
+
+/// Write a pull request description from the branch's commits and diff against `base`
+pub async fn generate_pr_desc(
+    base: &str,
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<PrDescription> {
+    let vars = HashMap::from([
+        ("language", app_config.language.prompt_name().to_string()),
+        ("base", base.to_string()),
+        ("branch", context.branch.clone().unwrap_or_default()),
+        ("commits", context.history_text()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        (
+            "issue",
+            context
+                .issue
+                .as_ref()
+                .map(|(id, _)| id.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "issue_title",
+            context
+                .issue
+                .as_ref()
+                .map(|(_, t)| t.clone())
+                .unwrap_or_default(),
+        ),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("pr-desc")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    Ok(PrDescription::parse(
+        &ask(&app_config.llm_config, &prompt).await?,
+    ))
+}
+
+/// Type list shown to the LLM, one `- name: desc` per line
+fn types_text(types: &[CommitTypeConfig]) -> String {
+    types
+        .iter()
+        .map(|t| format!("- {}: {}", t.name, t.desc))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Ask the LLM to group the staged files into logical commits
+pub async fn plan_split(
+    types: &[CommitTypeConfig],
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<Vec<SplitGroup>> {
+    let vars = HashMap::from([
+        ("types", types_text(types)),
+        ("language", app_config.language.prompt_name().to_string()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("split")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    parse_plan(&ask(&app_config.llm_config, &prompt).await?)
+}
+
+/// Ask the LLM for likely bugs, debug leftovers, TODOs and missing tests in a diff
+pub async fn review_changes(
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<Vec<Finding>> {
+    let vars = HashMap::from([
+        ("language", app_config.language.prompt_name().to_string()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("review")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    parse_findings(&ask(&app_config.llm_config, &prompt).await?)
+}
+
+/// Plain-language explanation of commits from their messages and combined diff
+pub async fn explain_changes(
+    commits: &str,
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<String> {
+    let vars = HashMap::from([
+        ("language", app_config.language.prompt_name().to_string()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        ("commits", commits.to_string()),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("explain")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    Ok(ask(&app_config.llm_config, &prompt)
+        .await?
+        .trim()
+        .to_string())
+}
+
+/// Suggest `(type, description)` for a branch from a description and/or a diff
+pub async fn suggest_branch(
+    types: &[CommitTypeConfig],
+    description: &str,
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<(String, String)> {
+    let vars = HashMap::from([
+        ("types", types_text(types)),
+        ("description", description.to_string()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("branch")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    Ok(parse_typed_line(
+        &ask(&app_config.llm_config, &prompt).await?,
+    ))
+}
+
+/// Suggest `(type, subject)` for one commit replacing the commits in `context.history`
+pub async fn squash_message(
+    types: &[CommitTypeConfig],
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<(String, String)> {
+    let vars = HashMap::from([
+        ("types", types_text(types)),
+        ("language", app_config.language.prompt_name().to_string()),
+        ("commits", context.history_text()),
+        ("notes", context.notes.clone().unwrap_or_default()),
+        (
+            "issue",
+            context
+                .issue
+                .as_ref()
+                .map(|(id, _)| id.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "issue_title",
+            context
+                .issue
+                .as_ref()
+                .map(|(_, t)| t.clone())
+                .unwrap_or_default(),
+        ),
+        ("diff", git_diff_content.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("squash")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    Ok(parse_typed_line(
+        &ask(&app_config.llm_config, &prompt).await?,
+    ))
+}
+
+/// `(type, text)` from the first line of a `<type>: <text>` reply; the type is empty
+/// when the reply has none
+fn parse_typed_line(reply: &str) -> (String, String) {
+    let line = reply
+        .trim()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_matches('`');
+    match line.split_once(':') {
+        Some((commit_type, text)) => (commit_type.trim().to_string(), text.trim().to_string()),
+        None => (String::new(), line.trim().to_string()),
+    }
+}
+
+/// Rewrite a generated changelog section as release notes
+pub async fn polish_changelog(section: &str, app_config: &AppConfig) -> anyhow::Result<String> {
+    let vars = HashMap::from([
+        ("language", app_config.language.prompt_name().to_string()),
+        ("changelog", section.to_string()),
+    ]);
+    let mut prompt = PromptTemplate::load("changelog")?.render(&vars);
+    prompt.user = prepare_for_llm(&prompt.user, &app_config.redaction)?;
+    let polished = ask(&app_config.llm_config, &prompt).await?;
+    Ok(format!("{}\n", polished.trim()))
+}
+
+pub async fn generate_msg(
+    commit_type: &CommitTypeConfig,
+    git_diff_content: &str,
+    context: &PromptContext,
+    app_config: &AppConfig,
+) -> anyhow::Result<String> {
+    let llm_config = &app_config.llm_config;
+    if !llm_config.enable {
+        return Ok("".into());
+    }
+    let prompt = commit_prompt(commit_type, git_diff_content, context, app_config)?;
+    let cache = ResponseCache::open(&app_config.cache)?;
+    let key = ResponseCache::key(llm_config, &prompt);
+    if let Some(reply) = cache.as_ref().and_then(|cache| cache.get(&key)) {
+        return Ok(reply);
+    }
+    let reply = ask(llm_config, &prompt).await?.trim().to_string();
+    if let Some(cache) = &cache {
+        // a failed write only costs the next run another request
+        let _ = cache.put(&key, &reply);
+    }
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_typed_line() {
+        assert_eq!(
+            parse_typed_line("`feat: add retry to backend`\nbecause..."),
+            ("feat".to_string(), "add retry to backend".to_string())
+        );
+        assert_eq!(
+            parse_typed_line(" add retry "),
+            (String::new(), "add retry".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_pr_description() {
+        let desc = PrDescription::parse(
+            "# Title: Add changelog generation\n\n## Summary\nGroups commits by type.\n",
+        );
+        assert_eq!(desc.title, "Add changelog generation");
+        assert_eq!(desc.body, "## Summary\nGroups commits by type.");
+        assert_eq!(PrDescription::parse("Fix typo").body, "");
+    }
 }
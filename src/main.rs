@@ -1,15 +1,46 @@
 mod app_config;
+mod branch;
+mod breaking;
+mod cache;
+mod changelog;
 pub mod commit_types;
+mod context;
 mod customer_llm_backend;
+mod diff;
 mod git_utils;
+mod history;
+mod i18n;
+mod issue;
 mod llm;
+mod message;
+mod ollama;
+mod privacy;
+mod prompt;
+mod redact;
+mod release;
+mod review;
+mod split;
+mod tracker;
+mod usage;
+mod wire;
 
-use crate::app_config::load_app_config;
-use crate::commit_types::load_config;
-use crate::git_utils::get_diff_content;
-use crate::llm::generate_msg;
-use clap::Parser;
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use crate::app_config::{
+    load_app_config, save_llm_config, AppConfig, LLMConfig, LLMProvider, WireFormat,
+};
+use crate::commit_types::{load_config, BreakingCheck, Config, Severity};
+use crate::context::{allocate_budget, PromptContext};
+use crate::git_utils::{
+    default_base_branch, get_commit_diff, get_diff_between, get_index_diff, get_last_tag, get_log,
+    get_range_diff, get_repo_root, get_staged_diff, get_worktree_diff, git_output,
+};
+use crate::i18n::{fill, tr};
+use crate::llm::{
+    available_models, breaking_change, commit_prompt, explain_changes, generate_msg,
+    generate_pr_desc, plan_split, polish_changelog, review_changes, squash_message, suggest_branch,
+    ModelInfo,
+};
+use crate::message::{body_without_footer, commit_args, trailers, CommitMessage};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use std::io::IsTerminal;
 use std::process::Command;
 
@@ -19,12 +50,9 @@ fn is_tty() -> bool {
     std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
 }
 
-#[derive(Parser, Debug)]
-#[command(name = "gcw")]
-#[command(about = "AI-powered Git Commit Wrapper", long_about = None)]
-enum Cli {
-    #[command(external_subcommand)]
-    Git(Vec<String>),
+/// Prompts are drawn on stderr, so `gcw msg` can still ask questions while stdout is piped.
+fn can_prompt() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
 }
 
 #[derive(Debug)]
@@ -34,20 +62,184 @@ enum GitCommand {
         message: Option<String>,
         patch: bool,
         amend: bool,
+        dry_run: bool,
+        /// `--split`: let the LLM group the staged files into several commits
+        split: bool,
+        /// `--no-cache`: ask the LLM even when a cached message exists
+        no_cache: bool,
         extra_args: Vec<String>,
     },
+    /// `gcw msg [--type <name>] [--issue <id>] [--breaking <desc>] [--no-cache]`: print a generated message to stdout
+    Msg {
+        commit_type: Option<String>,
+        issue: Option<String>,
+        breaking: Option<String>,
+        no_cache: bool,
+    },
+    /// `gcw changelog [--from <rev>] [--to <rev>] [--polish] [--dry-run]`
+    Changelog {
+        from: Option<String>,
+        to: Option<String>,
+        polish: bool,
+        dry_run: bool,
+    },
+    /// `gcw release [--pre <id>] [--dry-run]`: bump the version and tag it
+    Release {
+        pre: Option<String>,
+        dry_run: bool,
+    },
+    /// `gcw branch new <description>` / `gcw branch suggest`: create a branch named by
+    /// the configured pattern, from the description or the current diff
+    Branch {
+        description: Option<String>,
+        commit_type: Option<String>,
+        issue: Option<String>,
+        dry_run: bool,
+    },
+    /// `gcw pr-desc [--base <branch>] [--output <file>]`: describe the current branch
+    PrDesc {
+        base: Option<String>,
+        output: Option<String>,
+    },
+    /// `gcw reword [<rev-range>] [--dry-run] [--no-cache]`: regenerate the messages of existing commits
+    Reword {
+        range: String,
+        dry_run: bool,
+        no_cache: bool,
+    },
+    /// `gcw squash <base> [--dry-run]`: replace the commits after `base` with one
+    Squash {
+        base: Option<String>,
+        dry_run: bool,
+    },
+    /// `gcw merge --squash <branch> [--dry-run]`: squash-merge with a synthesized message
+    MergeSquash {
+        merge_args: Vec<String>,
+        dry_run: bool,
+    },
+    /// `gcw review [--block-on <severity>] [--install-hook] [--hook]`: review the staged changes
+    Review {
+        block_on: Option<String>,
+        install_hook: bool,
+        /// Run from the pre-commit hook: errors are reported but never block the commit
+        hook: bool,
+    },
+    /// `gcw explain [<rev> | <from>..<to>]`: explain commits in plain language
+    Explain {
+        rev: String,
+    },
+    /// `gcw cache clear`: remove the cached LLM responses
+    CacheClear,
+    /// `gcw usage [--by day|model|repo] [--since YYYY-MM-DD]`: token usage and cost
+    Usage {
+        by: Option<String>,
+        since: Option<String>,
+    },
+    /// `gcw models [--provider <name>]`: list the models a provider offers
+    Models {
+        provider: Option<String>,
+    },
+    /// `gcw config init`: choose the provider, key and model interactively
+    ConfigInit,
+    /// `gcw prompt show [--type <name>]`: print the rendered commit prompt
+    PromptShow {
+        commit_type: Option<String>,
+    },
     Other(Vec<String>),
 }
 
 impl GitCommand {
     fn parse(args: Vec<String>) -> Self {
-        if args.is_empty() || args[0] != "commit" {
-            return GitCommand::Other(args);
+        match args.first().map(String::as_str) {
+            Some("commit") => Self::parse_commit(args),
+            Some("msg") => Self::parse_msg(args),
+            Some("branch")
+                if matches!(args.get(1).map(String::as_str), Some("new" | "suggest")) =>
+            {
+                let rest = &args[2..];
+                GitCommand::Branch {
+                    description: (args[1] == "new").then(|| {
+                        positional_args(rest, &["--type", "-t", "--issue", "-i"]).join(" ")
+                    }),
+                    commit_type: flag_value(rest, "--type", "-t"),
+                    issue: flag_value(rest, "--issue", "-i"),
+                    dry_run: rest.iter().any(|a| a == "--dry-run"),
+                }
+            }
+            Some("pr-desc") => GitCommand::PrDesc {
+                base: flag_value(&args[1..], "--base", "-b"),
+                output: flag_value(&args[1..], "--output", "-o"),
+            },
+            Some("release") => GitCommand::Release {
+                pre: flag_value(&args[1..], "--pre", "-p"),
+                dry_run: args.iter().any(|a| a == "--dry-run"),
+            },
+            Some("changelog") => GitCommand::Changelog {
+                from: flag_value(&args[1..], "--from", "-f"),
+                to: flag_value(&args[1..], "--to", "-t"),
+                polish: args.iter().any(|a| a == "--polish"),
+                dry_run: args.iter().any(|a| a == "--dry-run"),
+            },
+            Some("reword") => GitCommand::Reword {
+                range: positional_args(&args[1..], &[])
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| "HEAD".to_string()),
+                dry_run: args.iter().any(|a| a == "--dry-run"),
+                no_cache: args.iter().any(|a| a == "--no-cache"),
+            },
+            Some("squash") => GitCommand::Squash {
+                base: positional_args(&args[1..], &[]).into_iter().next(),
+                dry_run: args.iter().any(|a| a == "--dry-run"),
+            },
+            Some("merge") if args.iter().any(|a| a == "--squash") => GitCommand::MergeSquash {
+                merge_args: args[1..]
+                    .iter()
+                    .filter(|a| *a != "--squash" && *a != "--dry-run")
+                    .cloned()
+                    .collect(),
+                dry_run: args.iter().any(|a| a == "--dry-run"),
+            },
+            Some("review") => GitCommand::Review {
+                block_on: flag_value(&args[1..], "--block-on", "-b"),
+                install_hook: args.iter().any(|a| a == "--install-hook"),
+                hook: args.iter().any(|a| a == "--hook"),
+            },
+            Some("explain") => GitCommand::Explain {
+                rev: positional_args(&args[1..], &[])
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| "HEAD".to_string()),
+            },
+            Some("cache") if args.get(1).map(String::as_str) == Some("clear") => {
+                GitCommand::CacheClear
+            }
+            Some("models") => GitCommand::Models {
+                provider: flag_value(&args[1..], "--provider", "-p"),
+            },
+            Some("config") if args.get(1).map(String::as_str) == Some("init") => {
+                GitCommand::ConfigInit
+            }
+            Some("usage") => GitCommand::Usage {
+                by: flag_value(&args[1..], "--by", "-b"),
+                since: flag_value(&args[1..], "--since", "-s"),
+            },
+            Some("prompt") if args.get(1).map(String::as_str) == Some("show") => {
+                GitCommand::PromptShow {
+                    commit_type: flag_value(&args[2..], "--type", "-t"),
+                }
+            }
+            _ => GitCommand::Other(args),
         }
+    }
 
+    fn parse_commit(args: Vec<String>) -> Self {
         let mut all = false;
         let mut patch = false;
         let mut amend = false;
+        let mut dry_run = false;
+        let mut split = false;
+        let mut no_cache = false;
         let mut message = None;
         let mut extra_args = Vec::new();
         let mut skip_next = false;
@@ -62,6 +254,9 @@ impl GitCommand {
                 "--all" | "-a" => all = true,
                 "--patch" | "-p" => patch = true,
                 "--amend" => amend = true,
+                "--dry-run" => dry_run = true,
+                "--split" => split = true,
+                "--no-cache" => no_cache = true,
                 "-m" | "--message" => {
                     if i + 1 < args.len() {
                         message = Some(args[i + 1].clone());
@@ -83,9 +278,207 @@ impl GitCommand {
             message,
             patch,
             amend,
+            dry_run,
+            split,
+            no_cache,
             extra_args,
         }
     }
+
+    fn parse_msg(args: Vec<String>) -> Self {
+        GitCommand::Msg {
+            commit_type: flag_value(&args[1..], "--type", "-t"),
+            issue: flag_value(&args[1..], "--issue", "-i"),
+            breaking: flag_value(&args[1..], "--breaking", "-b"),
+            no_cache: args.iter().any(|a| a == "--no-cache"),
+        }
+    }
+}
+
+fn select_commit_type(config: &Config) -> anyhow::Result<usize> {
+    let selects: Vec<String> = config.types.iter().map(|x| x.menu_string()).collect();
+    Ok(Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().select_commit_type)
+        .items(&selects)
+        .interact()?)
+}
+
+/// Ask for the issue number, pre-filled with the one found in the branch name
+fn prompt_issue_number(config: &Config) -> anyhow::Result<String> {
+    let detected = issue::detect_issue(&config.issue).ok().flatten();
+    Ok(Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().issue_prompt)
+        .with_initial_text(detected.unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?)
+}
+
+/// Ask whether the commit is breaking, defaulting to yes when `hint` is set.
+///
+/// `None` when it is not; otherwise the footer description, possibly empty.
+fn prompt_breaking(hint: Option<String>) -> anyhow::Result<Option<String>> {
+    if let Some(hint) = &hint {
+        eprintln!("{}", fill(tr().breaking_detected, &[hint]));
+    }
+    let breaking = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().breaking_prompt)
+        .default(hint.is_some())
+        .interact()?;
+    if !breaking {
+        return Ok(None);
+    }
+    let desc: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().breaking_desc_prompt)
+        .with_initial_text(hint.unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?;
+    Ok(Some(desc.trim().to_string()))
+}
+
+/// Description of a likely breaking change in the staged diff, per `[breaking] check`
+async fn suggest_breaking(
+    config: &Config,
+    app_config: &AppConfig,
+) -> anyhow::Result<Option<String>> {
+    if matches!(config.breaking.check, BreakingCheck::Off) {
+        return Ok(None);
+    }
+    let staged_diff = get_index_diff()?;
+    breaking_in(staged_diff, config, app_config).await
+}
+
+/// [`suggest_breaking`] for the already read `staged_diff`
+async fn breaking_in(
+    mut staged_diff: diff::StagedDiff,
+    config: &Config,
+    app_config: &AppConfig,
+) -> anyhow::Result<Option<String>> {
+    // Nothing is staged yet for `commit -a` or `commit --amend`
+    if staged_diff.is_empty() {
+        return Ok(None);
+    }
+    let use_llm =
+        matches!(config.breaking.check, BreakingCheck::Llm) && app_config.llm_config.enable;
+    let mut app_config = app_config.clone();
+    if use_llm {
+        // The findings name files and items, so they must not come from excluded files
+        privacy::enforce(
+            &mut staged_diff,
+            &config.privacy,
+            &mut app_config.llm_config,
+        )?;
+    }
+    let findings = breaking::detect(&staged_diff);
+    if !use_llm {
+        return Ok((!findings.is_empty()).then(|| findings.join("; ")));
+    }
+    let git_diff_content = staged_diff.render(&app_config.diff, app_config.diff.max_tokens)?;
+    breaking_change(&git_diff_content, &findings, &app_config).await
+}
+
+/// Value of `--name <v>` / `--name=<v>` (or its short form) in `args`
+fn flag_value(args: &[String], long: &str, short: &str) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == long || arg == short {
+            return iter.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(long).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Arguments that are neither flags nor the values of `value_flags`
+fn positional_args(args: &[String], value_flags: &[&str]) -> Vec<String> {
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if value_flags.contains(&arg.as_str()) {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            positional.push(arg.clone());
+        }
+    }
+    positional
+}
+
+/// Pick the commit type from `--type`, or interactively when a terminal is available
+fn resolve_commit_type(config: &Config, name: Option<String>) -> anyhow::Result<usize> {
+    match name {
+        Some(name) => config
+            .types
+            .iter()
+            .position(|t| t.name == name)
+            .ok_or_else(|| anyhow::anyhow!(fill(tr().unknown_type, &[&name]))),
+        None if can_prompt() => select_commit_type(config),
+        None => anyhow::bail!(tr().no_terminal),
+    }
+}
+
+/// Add the tracker's title for `issue` to the prompt context, when a tracker is configured
+async fn attach_issue(context: &mut PromptContext, app_config: &AppConfig, issue: Option<&str>) {
+    if let (Some(issue), Some(tracker)) =
+        (issue.filter(|i| !i.trim().is_empty()), &app_config.tracker)
+    {
+        if let Some(title) = tracker::lookup_issue_title(tracker, issue).await {
+            context.issue = Some((issue::issue_reference(issue), title));
+        }
+    }
+}
+
+/// Staged diff and project context rendered within the token budget.
+///
+/// Applies the privacy policy first, which may switch `app_config.llm_config` to a
+/// local provider. `None` when nothing is staged.
+async fn gather_commit_input(
+    config: &Config,
+    app_config: &mut AppConfig,
+    issue: Option<&str>,
+) -> anyhow::Result<Option<(String, PromptContext)>> {
+    let mut staged_diff = get_staged_diff()?;
+    if staged_diff.is_empty() {
+        return Ok(None);
+    }
+    privacy::enforce(
+        &mut staged_diff,
+        &config.privacy,
+        &mut app_config.llm_config,
+    )?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().generating,
+            &[
+                &staged_diff.files.len(),
+                &staged_diff.insertions(),
+                &staged_diff.deletions()
+            ]
+        )
+    );
+    let mut context = PromptContext::collect(&app_config.context);
+    attach_issue(&mut context, app_config, issue).await;
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = staged_diff.render(&app_config.diff, diff_budget)?;
+    Ok(Some((git_diff_content, context)))
+}
+
+/// Generate a subject from the staged diff, `None` when nothing is staged
+async fn generate_subject(
+    config: &Config,
+    mut app_config: AppConfig,
+    selection: usize,
+    issue: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let Some((git_diff_content, context)) =
+        gather_commit_input(config, &mut app_config, issue).await?
+    else {
+        return Ok(None);
+    };
+    let commit_type = &config.types[selection];
+    let msg = generate_msg(commit_type, &git_diff_content, &context, &app_config).await?;
+    Ok(Some(msg))
 }
 
 #[tokio::main]
@@ -99,8 +492,22 @@ async fn main() -> anyhow::Result<()> {
             message,
             patch,
             amend,
+            dry_run,
+            split,
+            no_cache,
             extra_args,
         } => {
+            if no_cache {
+                cache::disable();
+            }
+            if split {
+                if all || patch || amend {
+                    anyhow::bail!(tr().split_flags);
+                }
+                let app_config = load_app_config()?;
+                let config = load_config()?;
+                return run_split(&config, app_config, dry_run, extra_args).await;
+            }
             let mut base_args = Vec::new();
             if all {
                 base_args.push("--all".to_string());
@@ -112,56 +519,1182 @@ async fn main() -> anyhow::Result<()> {
                 base_args.push("--amend".to_string());
             }
 
-            if let Some(msg) = message {
-                let config = load_config()?;
-                let selects: Vec<String> = config.types.iter().map(|x| x.show_string()).collect();
-                let selection = Select::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Select commit type")
-                    .items(&selects)
-                    .interact()?;
-                let issue_number: String = Input::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Issue number (optional, press Enter to skip)")
-                    .allow_empty(true)
-                    .interact_text()?;
-                let msg = if msg.is_empty() {
-                    let git_diff_content = get_diff_content()?;
-                    if git_diff_content.is_empty() {
-                        println!("No changes to commit.");
+            match message {
+                Some(msg) if is_tty() => {
+                    let app_config = load_app_config()?;
+                    let config = load_config()?;
+                    let selection = select_commit_type(&config)?;
+                    let issue_number = prompt_issue_number(&config)?;
+                    let breaking = prompt_breaking(suggest_breaking(&config, &app_config).await?)?;
+                    let msg = if msg.is_empty() {
+                        match generate_subject(&config, app_config, selection, Some(&issue_number))
+                            .await?
+                        {
+                            Some(msg) => msg,
+                            None => {
+                                println!("{}", tr().no_changes);
+                                return Ok(());
+                            }
+                        }
+                    } else {
+                        msg
+                    };
+
+                    let prefixed_msg = CommitMessage::new(
+                        &config.types[selection],
+                        Some(issue_number),
+                        msg,
+                        config.emoji.enable,
+                    )
+                    .with_issue_config(&config.issue)
+                    .with_breaking(breaking)
+                    .render();
+
+                    if dry_run {
+                        println!("{}", prefixed_msg);
                         return Ok(());
                     }
-                    let app_config = load_app_config()?;
-                    generate_msg(&selects[selection], &git_diff_content, &app_config.llm_config).await?
-                } else {
-                    msg
-                };
-
-
-                let commit_type = &config.types[selection];
-                let prefix = if issue_number.is_empty() {
-                    format!("{}: {}", commit_type.name, commit_type.emoji)
-                } else {
-                    format!("{}(#{}): {}", commit_type.name, issue_number, commit_type.emoji)
-                };
-                let prefixed_msg = format!("{} {}", prefix, msg);
-
-                let mut args = vec!["commit".to_string()];
-                args.extend(base_args);
-                args.push("-m".to_string());
-                args.push(prefixed_msg);
-                args.extend(extra_args);
-
-                execute_git(&args)
+                    execute_git(&commit_args(base_args, prefixed_msg, extra_args))
+                }
+                // An empty message asks for a generated one, which needs the type prompt
+                Some(msg) if msg.is_empty() => anyhow::bail!(tr().commit_no_terminal),
+                // Non-interactive: the message is used unchanged
+                Some(msg) if dry_run => {
+                    println!("{}", msg);
+                    Ok(())
+                }
+                Some(msg) => execute_git(&commit_args(base_args, msg, extra_args)),
+                None => {
+                    let mut args = vec!["commit".to_string()];
+                    args.extend(base_args);
+                    if dry_run {
+                        args.push("--dry-run".to_string());
+                    }
+                    args.extend(extra_args);
+                    execute_git(&args)
+                }
+            }
+        }
+        GitCommand::Msg {
+            commit_type,
+            issue,
+            breaking,
+            no_cache,
+        } => {
+            if no_cache {
+                cache::disable();
+            }
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            let selection = resolve_commit_type(&config, commit_type)?;
+            let issue = match issue {
+                Some(issue) => Some(issue),
+                None if can_prompt() => Some(prompt_issue_number(&config)?),
+                None => issue::detect_issue(&config.issue)?,
+            };
+            let breaking = match breaking {
+                Some(desc) => Some(desc),
+                None if can_prompt() => {
+                    prompt_breaking(suggest_breaking(&config, &app_config).await?)?
+                }
+                None => None,
+            };
+            let Some(subject) =
+                generate_subject(&config, app_config, selection, issue.as_deref()).await?
+            else {
+                anyhow::bail!(tr().no_staged_changes);
+            };
+            if subject.is_empty() {
+                anyhow::bail!(tr().llm_disabled);
+            }
+            let msg = CommitMessage::new(
+                &config.types[selection],
+                issue,
+                subject,
+                config.emoji.enable,
+            )
+            .with_issue_config(&config.issue)
+            .with_breaking(breaking);
+            println!("{}", msg.render());
+            Ok(())
+        }
+        GitCommand::Changelog {
+            from,
+            to,
+            polish,
+            dry_run,
+        } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            let to = to.unwrap_or_else(|| "HEAD".to_string());
+            let commits = changelog::collect_commits(&config, from.as_deref(), &to)?;
+            if commits.is_empty() {
+                anyhow::bail!(fill(tr().no_commits, &[&to]));
+            }
+            let (title, date) = if to == "HEAD" {
+                ("Unreleased".to_string(), None)
             } else {
-                let mut args = vec!["commit".to_string()];
-                args.extend(base_args);
-                args.extend(extra_args);
-                execute_git(&args)
+                let date = git_output(&["log", "-1", "--format=%cs", &to])?;
+                let version = to.strip_prefix(config.release.tag_prefix.as_str());
+                (version.unwrap_or(&to).to_string(), Some(date))
+            };
+            let mut section = changelog::ChangelogWriter::for_repo(&config).section(
+                &title,
+                date.as_deref(),
+                &commits,
+            );
+            if polish {
+                if !app_config.llm_config.enable {
+                    anyhow::bail!(tr().llm_disabled);
+                }
+                section = polish_changelog(&section, &app_config).await?;
+            }
+            if dry_run {
+                print!("{}", section);
+                return Ok(());
+            }
+            let path = changelog::write_section(&config, &section, false)?;
+            eprintln!("{}", fill(tr().changelog_written, &[&path.display()]));
+            Ok(())
+        }
+        GitCommand::Release { pre, dry_run } => {
+            load_app_config()?;
+            let config = load_config()?;
+            run_release(&config, pre.as_deref(), dry_run)
+        }
+        GitCommand::Branch {
+            description,
+            commit_type,
+            issue,
+            dry_run,
+        } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            run_branch(
+                &config,
+                app_config,
+                description,
+                commit_type,
+                issue,
+                dry_run,
+            )
+            .await
+        }
+        GitCommand::PrDesc { base, output } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            run_pr_desc(&config, app_config, base, output).await
+        }
+        GitCommand::Reword {
+            range,
+            dry_run,
+            no_cache,
+        } => {
+            if no_cache {
+                cache::disable();
+            }
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            run_reword(&config, app_config, &range, dry_run).await
+        }
+        GitCommand::Squash { base, dry_run } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            let Some(base) = base else {
+                anyhow::bail!(tr().squash_usage);
+            };
+            let range = format!("{}..HEAD", base);
+            let (message, count) =
+                squash_commit_message(&config, app_config, &range, &base, "HEAD").await?;
+            if !confirm_message(&message, dry_run)? {
+                return Ok(());
+            }
+            history::squash_onto(&base, &message)?;
+            eprintln!("{}", fill(tr().squash_done, &[&count]));
+            Ok(())
+        }
+        GitCommand::MergeSquash {
+            merge_args,
+            dry_run,
+        } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            let value_flags = [
+                "-m",
+                "-F",
+                "--file",
+                "-s",
+                "--strategy",
+                "-X",
+                "--strategy-option",
+            ];
+            let Some(branch) = positional_args(&merge_args, &value_flags).pop() else {
+                anyhow::bail!(tr().squash_usage);
+            };
+            let base = git_output(&["merge-base", "HEAD", &branch])?;
+            let range = format!("HEAD..{}", branch);
+            let (message, _) =
+                squash_commit_message(&config, app_config, &range, &base, &branch).await?;
+            if !confirm_message(&message, dry_run)? {
+                return Ok(());
+            }
+            if !history::merge_squash(&merge_args, &message)? {
+                anyhow::bail!(tr().squash_conflicts);
+            }
+            execute_git(&commit_args(Vec::new(), message, Vec::new()))
+        }
+        GitCommand::Review {
+            block_on,
+            install_hook,
+            hook,
+        } => {
+            if install_hook {
+                load_app_config()?;
+                return install_review_hook();
+            }
+            let review = async {
+                let app_config = load_app_config()?;
+                let config = load_config()?;
+                let block_on =
+                    match block_on {
+                        Some(name) => Some(Severity::parse(&name).ok_or_else(|| {
+                            anyhow::anyhow!(fill(tr().unknown_severity, &[&name]))
+                        })?),
+                        None => config.review.block_on,
+                    };
+                let blocking = run_review(&config, app_config, block_on).await?;
+                anyhow::Ok((blocking, block_on))
+            };
+            match review.await {
+                Ok((blocking, Some(threshold))) if blocking > 0 => {
+                    anyhow::bail!(fill(tr().review_blocked, &[&blocking, &threshold.label()]))
+                }
+                Ok(_) => Ok(()),
+                // A broken config or an unreachable LLM must not stop anyone from committing
+                Err(e) if hook => {
+                    eprintln!("{}", fill(tr().review_skipped, &[&e]));
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        GitCommand::Explain { rev } => {
+            let app_config = load_app_config()?;
+            let config = load_config()?;
+            run_explain(&config, app_config, &rev).await
+        }
+        GitCommand::CacheClear => {
+            load_app_config()?;
+            let cache = cache::ResponseCache::new(cache::cache_path()?, 0, 0);
+            eprintln!("{}", fill(tr().cache_cleared, &[&cache.clear()?]));
+            Ok(())
+        }
+        GitCommand::Models { provider } => {
+            let app_config = load_app_config()?;
+            let llm_config = match provider {
+                Some(name) => {
+                    let provider = LLMProvider::parse(&name)
+                        .ok_or_else(|| anyhow::anyhow!(fill(tr().unknown_provider, &[&name])))?;
+                    if provider == app_config.llm_config.provider {
+                        app_config.llm_config
+                    } else {
+                        LLMConfig::new(provider, true, None, None, None)
+                    }
+                }
+                None => app_config.llm_config,
+            };
+            let models = available_models(&llm_config).await?;
+            if models.is_empty() {
+                match llm_config.provider {
+                    LLMProvider::Ollama => eprintln!(
+                        "{}",
+                        fill(
+                            tr().ollama_no_models,
+                            &[
+                                &ollama::base_url(llm_config.url.as_deref()),
+                                &ollama::DEFAULT_MODEL
+                            ]
+                        )
+                    ),
+                    _ => eprintln!("{}", tr().no_models),
+                }
+                return Ok(());
             }
+            // the model `gcw` would use is marked with `*`
+            let current = current_model(&llm_config, &models);
+            for model in &models {
+                let in_use = current
+                    .as_deref()
+                    .is_some_and(|current| ollama::same_model(&model.name, current));
+                let line = format!(
+                    "{} {:<40} {:>8}",
+                    if in_use { "*" } else { " " },
+                    model.name,
+                    model.size.map(ollama::format_size).unwrap_or_default()
+                );
+                println!("{}", line.trim_end());
+            }
+            Ok(())
+        }
+        GitCommand::ConfigInit => {
+            let app_config = load_app_config()?;
+            run_config_init(app_config).await
+        }
+        GitCommand::Usage { by, since } => {
+            let app_config = load_app_config()?;
+            let by = match by {
+                Some(name) => usage::GroupBy::parse(&name)
+                    .ok_or_else(|| anyhow::anyhow!(fill(tr().unknown_usage_group, &[&name])))?,
+                None => usage::GroupBy::Day,
+            };
+            let entries: Vec<_> = usage::read_log()?
+                .into_iter()
+                .filter(|e| {
+                    since
+                        .as_ref()
+                        .is_none_or(|since| e.time.as_str() >= since.as_str())
+                })
+                .collect();
+            if entries.is_empty() {
+                eprintln!("{}", tr().usage_empty);
+                return Ok(());
+            }
+            let (rows, mut total) = usage::summarize(&entries, by, &app_config.usage.prices);
+            total.key = tr().usage_total.to_string();
+            // day, model, repo, then the counter columns
+            let labels = tr().usage_columns;
+            let mut columns = vec![match by {
+                usage::GroupBy::Day => labels[0],
+                usage::GroupBy::Model => labels[1],
+                usage::GroupBy::Repo => labels[2],
+            }];
+            columns.extend(&labels[3..]);
+            print!("{}", usage::render(&rows, &total, &columns));
+            if total.unpriced > 0 && total.cost.is_some() {
+                eprintln!("{}", fill(tr().usage_partial, &[&total.unpriced]));
+            }
+            Ok(())
+        }
+        GitCommand::PromptShow { commit_type } => {
+            let mut app_config = load_app_config()?;
+            let config = load_config()?;
+            let selection = resolve_commit_type(&config, commit_type)?;
+            let issue = issue::detect_issue(&config.issue)?;
+            let Some((git_diff_content, context)) =
+                gather_commit_input(&config, &mut app_config, issue.as_deref()).await?
+            else {
+                anyhow::bail!(tr().no_staged_changes);
+            };
+            let prompt = commit_prompt(
+                &config.types[selection],
+                &git_diff_content,
+                &context,
+                &app_config,
+            )?;
+            println!(
+                "--- system ---\n{}\n\n--- user ---\n{}",
+                prompt.system, prompt.user
+            );
+            Ok(())
         }
         GitCommand::Other(args) => execute_git(&args),
     }
 }
 
+/// Commit the staged changes as several logical commits planned by the LLM
+async fn run_split(
+    config: &Config,
+    mut app_config: AppConfig,
+    dry_run: bool,
+    extra_args: Vec<String>,
+) -> anyhow::Result<()> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    let staged = get_staged_diff()?;
+    let mut diff = staged.clone();
+    privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().generating,
+            &[&diff.files.len(), &diff.insertions(), &diff.deletions()]
+        )
+    );
+    let mut context = PromptContext::collect(&app_config.context);
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+    let plan = plan_split(&config.types, &git_diff_content, &context, &app_config).await?;
+    let plan = split::normalize_plan(plan, &staged, config)?;
+
+    let issue = if can_prompt() && !dry_run {
+        Some(prompt_issue_number(config)?)
+    } else {
+        issue::detect_issue(&config.issue)?
+    };
+    let groups: Vec<(String, Vec<String>)> = plan
+        .into_iter()
+        .map(|group| {
+            let commit_type = config
+                .types
+                .iter()
+                .find(|t| t.name == group.commit_type)
+                .unwrap_or(&config.types[0]);
+            let message = CommitMessage::new(
+                commit_type,
+                issue.clone(),
+                group.message,
+                config.emoji.enable,
+            )
+            .with_issue_config(&config.issue)
+            .render();
+            (message, group.files)
+        })
+        .collect();
+
+    println!("{}", fill(tr().split_plan, &[&groups.len()]));
+    for (i, (message, files)) in groups.iter().enumerate() {
+        println!("{}. {}", i + 1, message.lines().next().unwrap_or_default());
+        for file in files {
+            println!("     {}", file);
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !can_prompt() {
+        anyhow::bail!(tr().no_terminal);
+    }
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().split_confirm)
+        .default(true)
+        .interact()?;
+    if !confirmed {
+        return Ok(());
+    }
+    if let Err(err) = split::commit_groups(&groups, &staged, &extra_args) {
+        eprintln!("{}", tr().split_rollback);
+        return Err(err);
+    }
+    execute_git(&[
+        "log".to_string(),
+        "--oneline".to_string(),
+        format!("-{}", groups.len()),
+    ])
+}
+
+/// Regenerate the messages of the commits in `range` (a single rev means just that
+/// commit) from their own diffs, and rewrite the ones the user accepts
+async fn run_reword(
+    config: &Config,
+    app_config: AppConfig,
+    range: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    if !dry_run && !can_prompt() {
+        anyhow::bail!(tr().reword_no_terminal);
+    }
+    let range = if range.contains("..") {
+        range.to_string()
+    } else {
+        format!("{}^!", range)
+    };
+    let mut entries = get_log(&range)?;
+    if entries.is_empty() {
+        anyhow::bail!(fill(tr().no_commits, &[&range]));
+    }
+    entries.reverse();
+    let fallback_type = config
+        .types
+        .iter()
+        .position(|t| t.name == "chore")
+        .unwrap_or(0);
+
+    let mut rewrites = Vec::new();
+    for entry in &entries {
+        let old = changelog::ConventionalCommit::parse(entry, config);
+        let known_type = old
+            .commit_type
+            .as_deref()
+            .and_then(|name| config.types.iter().position(|t| t.name == name));
+        let selection = match known_type {
+            Some(selection) => selection,
+            None if can_prompt() && !dry_run => {
+                eprintln!("{} {}", old.short_hash(), entry.subject);
+                select_commit_type(config)?
+            }
+            None => fallback_type,
+        };
+
+        let mut diff = get_commit_diff(&entry.hash)?;
+        if diff.is_empty() {
+            continue;
+        }
+        let mut app_config = app_config.clone();
+        privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+        eprintln!(
+            "{}",
+            fill(
+                tr().generating,
+                &[&diff.files.len(), &diff.insertions(), &diff.deletions()]
+            )
+        );
+        let issue = old.issues.first().cloned();
+        let mut context = PromptContext::collect(&app_config.context);
+        attach_issue(&mut context, &app_config, issue.as_deref()).await;
+        let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+        let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+        let commit_type = &config.types[selection];
+        let subject = generate_msg(commit_type, &git_diff_content, &context, &app_config).await?;
+        let message = |subject: String| {
+            CommitMessage::new(commit_type, issue.clone(), subject, config.emoji.enable)
+                .with_issue_config(&config.issue)
+                .with_breaking(old.breaking.clone())
+                .with_body(body_without_footer(&entry.body))
+                .with_trailers(trailers(&entry.body))
+                .render()
+        };
+        let new_message = message(subject.clone());
+
+        println!("{}", old.short_hash());
+        println!("  - {}", entry.subject);
+        for line in new_message.lines() {
+            println!("  + {}", line);
+        }
+        if dry_run {
+            continue;
+        }
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(tr().reword_prompt)
+            .items(tr().reword_choices)
+            .default(0)
+            .interact()?;
+        match choice {
+            0 => rewrites.push((entry.hash.clone(), new_message)),
+            1 => {
+                let subject: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt(tr().reword_subject_prompt)
+                    .with_initial_text(subject)
+                    .interact_text()?;
+                rewrites.push((entry.hash.clone(), message(subject.trim().to_string())));
+            }
+            _ => {}
+        }
+    }
+
+    if rewrites.is_empty() {
+        return Ok(());
+    }
+    history::reword_commits(&rewrites)?;
+    eprintln!("{}", fill(tr().reword_done, &[&rewrites.len()]));
+    Ok(())
+}
+
+/// One message for the commits in `range` and their combined diff `from`..`to`,
+/// keeping their issues, breaking change notes and trailers. Also returns the
+/// number of commits.
+async fn squash_commit_message(
+    config: &Config,
+    mut app_config: AppConfig,
+    range: &str,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<(String, usize)> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    let entries = get_log(range)?;
+    if entries.is_empty() {
+        anyhow::bail!(fill(tr().no_commits, &[&range]));
+    }
+    let commits: Vec<changelog::ConventionalCommit> = entries
+        .iter()
+        .rev()
+        .map(|entry| changelog::ConventionalCommit::parse(entry, config))
+        .collect();
+
+    let mut diff = get_diff_between(from, to)?;
+    privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().generating_pr,
+            &[
+                &commits.len(),
+                &diff.files.len(),
+                &diff.insertions(),
+                &diff.deletions()
+            ]
+        )
+    );
+    let mut context = PromptContext::collect(&app_config.context);
+    context.history = entries.iter().rev().map(|e| e.subject.clone()).collect();
+    let issue = match commits.iter().find_map(|c| c.issues.first()) {
+        Some(issue) => Some(issue.clone()),
+        None => issue::detect_issue(&config.issue).ok().flatten(),
+    };
+    attach_issue(&mut context, &app_config, issue.as_deref()).await;
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+    let (commit_type, subject) =
+        squash_message(&config.types, &git_diff_content, &context, &app_config).await?;
+
+    let commit_type = config
+        .types
+        .iter()
+        .find(|t| t.name == commit_type)
+        .or_else(|| config.types.iter().find(|t| t.name == "chore"))
+        .unwrap_or(&config.types[0]);
+    let breaking = commits.iter().any(|c| c.breaking.is_some()).then(|| {
+        commits
+            .iter()
+            .filter_map(|c| c.breaking.as_deref())
+            .filter(|note| !note.is_empty())
+            .collect::<Vec<_>>()
+            .join("; ")
+    });
+    let mut footer = Vec::new();
+    for trailer in entries.iter().rev().flat_map(|e| trailers(&e.body)) {
+        if !footer.contains(&trailer) {
+            footer.push(trailer);
+        }
+    }
+    let message = CommitMessage::new(commit_type, issue, subject, config.emoji.enable)
+        .with_issue_config(&config.issue)
+        .with_breaking(breaking)
+        .with_trailers(footer)
+        .render();
+    Ok((message, commits.len()))
+}
+
+/// Print `message` and ask whether to use it; `false` on a dry run
+fn confirm_message(message: &str, dry_run: bool) -> anyhow::Result<bool> {
+    println!("{}", message);
+    if dry_run {
+        return Ok(false);
+    }
+    if !can_prompt() {
+        anyhow::bail!(tr().squash_no_terminal);
+    }
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr().squash_confirm)
+        .default(true)
+        .interact()?)
+}
+
+/// Print an explanation of commit `rev`, or of the commits in `from..to`
+async fn run_explain(config: &Config, mut app_config: AppConfig, rev: &str) -> anyhow::Result<()> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    let or_head = |rev: &str| if rev.is_empty() { "HEAD" } else { rev }.to_string();
+    let range = match rev.split_once("...") {
+        // `A...B` explains what B added since it forked from A, like a pull request
+        Some((from, to)) => Some((
+            git_output(&["merge-base", &or_head(from), &or_head(to)])?,
+            or_head(to),
+        )),
+        None => rev
+            .split_once("..")
+            .map(|(from, to)| (or_head(from), or_head(to))),
+    };
+    let (entries, mut diff) = match range {
+        Some((from, to)) => (
+            get_log(&format!("{}..{}", from, to))?,
+            get_diff_between(&from, &to)?,
+        ),
+        None => {
+            let mut entries = get_log(&format!("{}^!", rev))?;
+            if entries.is_empty() {
+                // A merge: the commits it brought in, matching its diff against the first parent
+                entries = get_log(&format!("{}^1..{}", rev, rev))?;
+            }
+            (entries, get_commit_diff(rev)?)
+        }
+    };
+    if entries.is_empty() {
+        anyhow::bail!(fill(tr().no_commits, &[&rev]));
+    }
+    privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().explaining,
+            &[
+                &entries.len(),
+                &diff.files.len(),
+                &diff.insertions(),
+                &diff.deletions()
+            ]
+        )
+    );
+    let commits = entries
+        .iter()
+        .rev()
+        .map(|e| {
+            format!(
+                "{} {}\n\n{}",
+                changelog::ConventionalCommit::parse(e, config).short_hash(),
+                e.subject,
+                e.body
+            )
+        })
+        .map(|text| text.trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let mut context = PromptContext::collect(&app_config.context);
+    context.history.clear();
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+    println!(
+        "{}",
+        explain_changes(&commits, &git_diff_content, &context, &app_config).await?
+    );
+    Ok(())
+}
+
+/// Print the LLM's findings on the staged changes and return how many reach `block_on`
+async fn run_review(
+    config: &Config,
+    mut app_config: AppConfig,
+    block_on: Option<Severity>,
+) -> anyhow::Result<usize> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    // Nothing staged (e.g. a message-only amend) passes, so the hook never gets in the way
+    let mut diff = get_index_diff()?;
+    if diff.is_empty() {
+        eprintln!("{}", tr().no_staged_changes);
+        return Ok(0);
+    }
+    privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().reviewing,
+            &[&diff.files.len(), &diff.insertions(), &diff.deletions()]
+        )
+    );
+    let mut context = PromptContext::collect(&app_config.context);
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+    let findings = review_changes(&git_diff_content, &context, &app_config).await?;
+    if findings.is_empty() {
+        eprintln!("{}", tr().review_clean);
+        return Ok(0);
+    }
+    print!("{}", review::report(&findings));
+    eprintln!("{}", fill(tr().review_summary, &[&findings.len()]));
+    Ok(block_on.map_or(0, |threshold| review::count_blocking(&findings, threshold)))
+}
+
+/// The configured model, or for Ollama the one `gcw` falls back to
+fn current_model(llm_config: &LLMConfig, models: &[ModelInfo]) -> Option<String> {
+    match llm_config.provider {
+        LLMProvider::Ollama => Some(ollama::pick_model(
+            models.iter().map(|m| m.name.as_str()),
+            llm_config.model.as_deref(),
+        )),
+        _ => llm_config.model.clone(),
+    }
+}
+
+async fn run_config_init(app_config: AppConfig) -> anyhow::Result<()> {
+    if !can_prompt() {
+        anyhow::bail!(tr().init_no_terminal);
+    }
+    let theme = ColorfulTheme::default();
+    let current = &app_config.llm_config;
+    let names: Vec<String> = LLMProvider::ALL
+        .iter()
+        .map(|p| format!("{:?}", p))
+        .collect();
+    let index = Select::with_theme(&theme)
+        .with_prompt(tr().init_provider)
+        .items(&names)
+        .default(
+            LLMProvider::ALL
+                .iter()
+                .position(|p| *p == current.provider)
+                .unwrap_or_default(),
+        )
+        .interact()?;
+    let provider = LLMProvider::ALL[index].clone();
+    let same_provider = provider == current.provider;
+
+    let wire_format = match provider {
+        LLMProvider::CUSTOM => {
+            let names: Vec<String> = WireFormat::ALL.iter().map(|f| format!("{:?}", f)).collect();
+            let default = WireFormat::ALL
+                .iter()
+                .position(|f| same_provider && *f == current.wire_format)
+                .unwrap_or_default();
+            WireFormat::ALL[Select::with_theme(&theme)
+                .with_prompt(tr().init_wire_format)
+                .items(&names)
+                .default(default)
+                .interact()?]
+        }
+        _ => WireFormat::default(),
+    };
+
+    let url = match provider {
+        LLMProvider::Ollama | LLMProvider::CUSTOM => {
+            let default =
+                current.url.clone().filter(|_| same_provider).or_else(|| {
+                    (provider == LLMProvider::Ollama).then(|| ollama::DEFAULT_URL.into())
+                });
+            let mut input = Input::<String>::with_theme(&theme)
+                .with_prompt(tr().init_url)
+                .allow_empty(provider == LLMProvider::Ollama);
+            if let Some(default) = default {
+                input = input.default(default);
+            }
+            Some(input.interact_text()?.trim().to_string()).filter(|url| !url.is_empty())
+        }
+        _ => current.url.clone().filter(|_| same_provider),
+    };
+
+    let api_key = match provider {
+        LLMProvider::Ollama => None,
+        _ => {
+            // an empty answer keeps the current key or leaves it to the environment
+            let fallback = current.api_key.clone().filter(|_| same_provider);
+            let hint = match (&fallback, provider.api_key_env()) {
+                (Some(key), _) => Some(format!("****{}", &key[key.len().saturating_sub(4)..])),
+                (None, Some(env)) if std::env::var(env).is_ok() => Some(format!("${}", env)),
+                _ => None,
+            };
+            let prompt = match &hint {
+                Some(hint) => fill(tr().init_api_key_keep, &[hint]),
+                None => tr().init_api_key.to_string(),
+            };
+            let key = dialoguer::Password::with_theme(&theme)
+                .with_prompt(prompt)
+                .allow_empty_password(true)
+                .interact()?;
+            Some(key.trim().to_string())
+                .filter(|key| !key.is_empty())
+                .or(fallback)
+        }
+    };
+
+    let mut llm_config = LLMConfig::new(provider, true, api_key, url, None);
+    llm_config.wire_format = wire_format;
+    if same_provider {
+        llm_config.headers = current.headers.clone();
+        llm_config.query = current.query.clone();
+        llm_config.proxy = current.proxy.clone();
+        llm_config.ca_bundle = current.ca_bundle.clone();
+    }
+    eprintln!("{}", tr().fetching_models);
+    let models = match available_models(&llm_config).await {
+        Ok(models) => models,
+        Err(err) => {
+            eprintln!("{}", fill(tr().models_failed, &[&err]));
+            Vec::new()
+        }
+    };
+    let current_model = current.model.clone().filter(|_| same_provider);
+    let picked = if models.is_empty() {
+        None
+    } else {
+        let mut items: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        items.push(tr().init_model_other);
+        let default = current_model
+            .as_deref()
+            .and_then(|model| {
+                models
+                    .iter()
+                    .position(|m| ollama::same_model(&m.name, model))
+            })
+            .unwrap_or_default();
+        let index = Select::with_theme(&theme)
+            .with_prompt(tr().init_model)
+            .items(&items)
+            .default(default)
+            .max_length(15)
+            .interact()?;
+        models.get(index).map(|m| m.name.clone())
+    };
+    llm_config.model = match picked {
+        Some(model) => Some(model),
+        None => {
+            let mut input = Input::<String>::with_theme(&theme)
+                .with_prompt(tr().init_model_input)
+                .allow_empty(llm_config.provider == LLMProvider::Ollama);
+            if let Some(model) = current_model {
+                input = input.default(model);
+            }
+            Some(input.interact_text()?.trim().to_string()).filter(|model| !model.is_empty())
+        }
+    };
+
+    let path = save_llm_config(&llm_config)?;
+    eprintln!("{}", fill(tr().config_saved, &[&path.display()]));
+    Ok(())
+}
+
+/// Add a `pre-commit` hook that runs `gcw review`, unless the repo already has one
+fn install_review_hook() -> anyhow::Result<()> {
+    let path = std::path::PathBuf::from(git_output(&[
+        "rev-parse",
+        "--git-path",
+        "hooks/pre-commit",
+    ])?);
+    if path.exists() {
+        anyhow::bail!(fill(tr().hook_exists, &[&path.display()]));
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(
+        &path,
+        "#!/bin/sh\n# Installed by gcw review --install-hook\ncommand -v gcw >/dev/null 2>&1 || exit 0\nexec gcw review --hook\n",
+    )?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    eprintln!("{}", fill(tr().hook_installed, &[&path.display()]));
+    Ok(())
+}
+
+/// Name a branch after `description`, or after the current diff when it is `None`,
+/// and switch to it
+async fn run_branch(
+    config: &Config,
+    mut app_config: AppConfig,
+    description: Option<String>,
+    commit_type: Option<String>,
+    issue: Option<String>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let llm_enabled = app_config.llm_config.enable;
+    let (suggested_type, slug_source) = match description {
+        Some(description) if commit_type.is_some() || !llm_enabled => (None, description),
+        Some(description) => {
+            let context = PromptContext::collect(&app_config.context);
+            let (suggested, _) =
+                suggest_branch(&config.types, &description, "", &context, &app_config).await?;
+            (Some(suggested), description)
+        }
+        None => {
+            if !llm_enabled {
+                anyhow::bail!(tr().llm_disabled);
+            }
+            let mut diff = match get_staged_diff() {
+                Ok(diff) if !diff.is_empty() => diff,
+                _ => get_worktree_diff()?,
+            };
+            if diff.is_empty() {
+                anyhow::bail!(tr().no_changes);
+            }
+            privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+            let mut context = PromptContext::collect(&app_config.context);
+            let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+            let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+            let (suggested, slug) =
+                suggest_branch(&config.types, "", &git_diff_content, &context, &app_config).await?;
+            (Some(suggested), slug)
+        }
+    };
+
+    let selection = match (commit_type, suggested_type) {
+        (Some(name), _) => resolve_commit_type(config, Some(name))?,
+        (None, Some(name)) => match config.types.iter().position(|t| t.name == name) {
+            Some(selection) => selection,
+            None => resolve_commit_type(config, None)?,
+        },
+        (None, None) => resolve_commit_type(config, None)?,
+    };
+    let issue = match issue {
+        Some(issue) => Some(issue),
+        None if can_prompt() && !dry_run => Some(prompt_issue_number(config)?),
+        None => None,
+    };
+    let Some(mut name) = branch::branch_name(
+        &config.branch,
+        &config.types[selection].name,
+        issue.as_deref(),
+        &slug_source,
+    ) else {
+        anyhow::bail!(fill(tr().branch_empty, &[&slug_source]));
+    };
+
+    if dry_run {
+        println!("{}", name);
+        return Ok(());
+    }
+    if can_prompt() {
+        name = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(tr().branch_name_prompt)
+            .with_initial_text(name)
+            .interact_text()?;
+    }
+    execute_git(&["switch".to_string(), "-c".to_string(), name])
+}
+
+/// Generate a pull request title and body for HEAD against `base`
+async fn run_pr_desc(
+    config: &Config,
+    mut app_config: AppConfig,
+    base: Option<String>,
+    output: Option<String>,
+) -> anyhow::Result<()> {
+    if !app_config.llm_config.enable {
+        anyhow::bail!(tr().llm_disabled);
+    }
+    let base = match base {
+        Some(base) => base,
+        None => default_base_branch()?,
+    };
+    let range = format!("{}..HEAD", base);
+    let commits = get_log(&range)?;
+    if commits.is_empty() {
+        anyhow::bail!(fill(tr().no_commits, &[&range]));
+    }
+    let mut diff = get_range_diff(&base)?;
+    privacy::enforce(&mut diff, &config.privacy, &mut app_config.llm_config)?;
+    eprintln!(
+        "{}",
+        fill(
+            tr().generating_pr,
+            &[
+                &commits.len(),
+                &diff.files.len(),
+                &diff.insertions(),
+                &diff.deletions()
+            ]
+        )
+    );
+
+    let mut context = PromptContext::collect(&app_config.context);
+    // The branch's own commits replace the repo's recent history
+    context.history = commits.iter().rev().map(|c| c.subject.clone()).collect();
+    let issue = issue::detect_issue(&config.issue).ok().flatten();
+    attach_issue(&mut context, &app_config, issue.as_deref()).await;
+    let diff_budget = allocate_budget(&mut context, app_config.diff.max_tokens);
+    let git_diff_content = diff.render(&app_config.diff, diff_budget)?;
+
+    let desc = generate_pr_desc(&base, &git_diff_content, &context, &app_config).await?;
+    let text = format!("{}\n\n{}\n", desc.title, desc.body);
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            eprintln!("{}", fill(tr().file_written, &[&path]));
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Compute the next version from commits since the last tag, update manifests and
+/// the changelog in a release commit, and create an annotated tag with the notes
+fn run_release(config: &Config, pre: Option<&str>, dry_run: bool) -> anyhow::Result<()> {
+    let root = get_repo_root()?;
+    let prefix = &config.release.tag_prefix;
+    let last_tag = get_last_tag("HEAD", prefix);
+    let manifests = if config.release.update_manifests {
+        release::Manifest::detect(&root)
+    } else {
+        Vec::new()
+    };
+    let manifest_version = manifests
+        .iter()
+        .find_map(|m| m.read_version().ok().flatten());
+    let current = last_tag
+        .as_deref()
+        .map(|tag| tag.strip_prefix(prefix.as_str()).unwrap_or(tag))
+        .or(manifest_version.as_deref())
+        .map(semver::Version::parse)
+        .transpose()?
+        .unwrap_or_else(|| semver::Version::new(0, 0, 0));
+
+    let commits = changelog::collect_commits(config, last_tag.as_deref(), "HEAD")?;
+    let since = last_tag
+        .clone()
+        .unwrap_or_else(|| "the first commit".to_string());
+    let Some(bump) = release::bump_for(&commits) else {
+        anyhow::bail!(fill(tr().nothing_to_release, &[&since]));
+    };
+    let next = release::next_version(&current, bump, pre)?;
+    let tag = format!("{}{}", prefix, next);
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let section = changelog::ChangelogWriter::for_repo(config).section(
+        &next.to_string(),
+        Some(&date),
+        &commits,
+    );
+
+    eprintln!(
+        "{}",
+        fill(tr().release_plan, &[&current, &next, &bump.label()])
+    );
+    if dry_run {
+        for manifest in &manifests {
+            eprintln!("  {}", manifest.path().display());
+        }
+        print!("{}", section);
+        return Ok(());
+    }
+
+    // The files are added whole, so edits of them not made here would land in the
+    // release commit
+    let mut touched: Vec<_> = manifests.iter().map(|m| m.path().to_path_buf()).collect();
+    touched.push(root.join("Cargo.lock"));
+    if config.release.changelog {
+        touched.push(root.join(&config.changelog.path));
+    }
+    for path in &touched {
+        let path = path.to_string_lossy();
+        if !git_output(&["status", "--porcelain", "--", &path])?.is_empty() {
+            anyhow::bail!(fill(tr().release_dirty, &[&path]));
+        }
+    }
+
+    let mut changed = Vec::new();
+    for manifest in &manifests {
+        let old = manifest.read_version().ok().flatten();
+        if manifest.write_version(&next)? {
+            changed.push(manifest.path().to_path_buf());
+        }
+        if let (release::Manifest::Cargo(path), Some(old)) = (manifest, old) {
+            let name = release::cargo_package_name(path).unwrap_or_default();
+            if release::update_cargo_lock(&root, &name, &old, &next)? {
+                changed.push(root.join("Cargo.lock"));
+            }
+        }
+    }
+    if config.release.changelog {
+        changed.push(changelog::write_section(config, &section, true)?);
+    }
+    // Only the files above go into the release commit, whatever else is staged;
+    // ignored files such as an untracked Cargo.lock are left out
+    let tracked: Vec<String> = changed
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .filter(|path| git_output(&["check-ignore", "-q", path]).is_err())
+        .collect();
+    if !tracked.is_empty() {
+        let mut add = vec!["add", "--"];
+        add.extend(tracked.iter().map(String::as_str));
+        git_output(&add)?;
+        let message = format!("chore(release): {}", tag);
+        let mut commit = vec!["commit", "-m", &message, "--"];
+        commit.extend(tracked.iter().map(String::as_str));
+        git_output(&commit)?;
+    }
+    git_output(&["tag", "-a", &tag, "--cleanup=verbatim", "-m", &section])?;
+    eprintln!("{}", fill(tr().release_tagged, &[&tag]));
+    Ok(())
+}
+
 fn execute_git(args: &[String]) -> anyhow::Result<()> {
     let mut binding = Command::new("git");
     let command = binding.args(args);
@@ -172,3 +1705,128 @@ fn execute_git(args: &[String]) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_commit_dry_run() {
+        let cmd = GitCommand::parse(args(&["commit", "-m", "", "--dry-run", "--no-verify"]));
+        let GitCommand::Commit {
+            message,
+            dry_run,
+            extra_args,
+            ..
+        } = cmd
+        else {
+            panic!("expected commit");
+        };
+        assert_eq!(message.as_deref(), Some(""));
+        assert!(dry_run);
+        assert_eq!(extra_args, ["--no-verify"]);
+    }
+
+    #[tokio::test]
+    async fn test_breaking_check_with_nothing_staged() {
+        // `gcw commit -am` and `gcw commit --amend -m` reach the check with nothing staged
+        let mut config: Config = toml::from_str("").unwrap();
+        let mut app_config = AppConfig::default();
+        app_config.llm_config.enable = true;
+        for check in [BreakingCheck::Heuristic, BreakingCheck::Llm] {
+            config.breaking.check = check;
+            let suggested = breaking_in(diff::StagedDiff::parse(""), &config, &app_config).await;
+            assert_eq!(suggested.unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_parse_branch_new() {
+        let cmd = GitCommand::parse(args(&["branch", "new", "add", "retry", "--issue", "12"]));
+        let GitCommand::Branch {
+            description, issue, ..
+        } = cmd
+        else {
+            panic!("expected branch");
+        };
+        assert_eq!(description.as_deref(), Some("add retry"));
+        assert_eq!(issue.as_deref(), Some("12"));
+        assert!(matches!(
+            GitCommand::parse(args(&["branch", "-d", "old"])),
+            GitCommand::Other(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_msg_flags() {
+        let cmd = GitCommand::parse(args(&[
+            "msg",
+            "--type",
+            "fix",
+            "--issue=42",
+            "--breaking",
+            "drop v1 API",
+            "--no-cache",
+        ]));
+        let GitCommand::Msg {
+            commit_type,
+            issue,
+            breaking,
+            no_cache,
+        } = cmd
+        else {
+            panic!("expected msg");
+        };
+        assert_eq!(commit_type.as_deref(), Some("fix"));
+        assert_eq!(issue.as_deref(), Some("42"));
+        assert_eq!(breaking.as_deref(), Some("drop v1 API"));
+        assert!(no_cache);
+    }
+
+    #[test]
+    fn test_parse_no_cache() {
+        let GitCommand::Commit {
+            no_cache,
+            extra_args,
+            ..
+        } = GitCommand::parse(args(&["commit", "--no-cache", "--no-verify"]))
+        else {
+            panic!("expected commit");
+        };
+        assert!(no_cache);
+        assert_eq!(extra_args, ["--no-verify"]);
+        let GitCommand::Other(passed) = GitCommand::parse(args(&["log", "--no-cache"])) else {
+            panic!("expected other");
+        };
+        assert_eq!(passed, ["log", "--no-cache"]);
+    }
+
+    #[test]
+    fn test_parse_merge_squash() {
+        let cmd = GitCommand::parse(args(&[
+            "merge",
+            "--squash",
+            "-X",
+            "ours",
+            "feature",
+            "--dry-run",
+        ]));
+        let GitCommand::MergeSquash {
+            merge_args,
+            dry_run,
+        } = cmd
+        else {
+            panic!("expected merge --squash");
+        };
+        assert_eq!(merge_args, ["-X", "ours", "feature"]);
+        assert!(dry_run);
+        assert!(matches!(
+            GitCommand::parse(args(&["merge", "feature"])),
+            GitCommand::Other(_)
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::commit_types::{CommitTypeConfig, IssueConfig, IssueStyle};
+use crate::issue::issue_reference;
+use derive_new::new;
+
+/// Final conventional commit message, independent of how it is delivered to git
+#[derive(Debug, new)]
+pub struct CommitMessage<'a> {
+    pub commit_type: &'a CommitTypeConfig,
+    pub issue: Option<String>,
+    pub subject: String,
+    pub emoji: bool,
+    #[new(default)]
+    pub issue_style: IssueStyle,
+    #[new(value = r#""Closes".to_string()"#)]
+    pub footer_keyword: String,
+    /// `Some` marks the commit with `!`; a non-empty description adds a `BREAKING CHANGE:` footer
+    #[new(default)]
+    pub breaking: Option<String>,
+    /// Free-form paragraphs between the subject and the footers
+    #[new(default)]
+    pub body: String,
+    /// Git trailers such as `Co-authored-by: ...`, kept after the other footers
+    #[new(default)]
+    pub trailers: Vec<String>,
+}
+
+impl CommitMessage<'_> {
+    pub fn with_issue_config(mut self, config: &IssueConfig) -> Self {
+        self.issue_style = config.style;
+        self.footer_keyword = config.footer_keyword.clone();
+        self
+    }
+
+    pub fn with_breaking(mut self, breaking: Option<String>) -> Self {
+        self.breaking = breaking;
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn with_trailers(mut self, trailers: Vec<String>) -> Self {
+        self.trailers = trailers;
+        self
+    }
+
+    /// e.g. `feat(#123): ✨ add quick filters`, with footers after a blank line
+    pub fn render(&self) -> String {
+        let issue = self
+            .issue
+            .as_deref()
+            .map(str::trim)
+            .filter(|issue| !issue.is_empty());
+        let scope = match (issue, self.issue_style) {
+            (Some(issue), IssueStyle::Hash) => format!("({})", issue_reference(issue)),
+            (Some(issue), IssueStyle::Key) => format!("({})", issue.trim_start_matches('#')),
+            _ => String::new(),
+        };
+        let marker = if self.breaking.is_some() { "!" } else { "" };
+        let mut msg = if self.emoji {
+            format!(
+                "{}{}{}: {} {}",
+                self.commit_type.name, scope, marker, self.commit_type.emoji, self.subject
+            )
+        } else {
+            format!(
+                "{}{}{}: {}",
+                self.commit_type.name, scope, marker, self.subject
+            )
+        };
+
+        let body = self.body.trim();
+        if !body.is_empty() {
+            msg.push_str("\n\n");
+            msg.push_str(body);
+        }
+
+        let mut footers = Vec::new();
+        if let Some(desc) = self.breaking.as_deref().map(str::trim) {
+            if !desc.is_empty() {
+                footers.push(format!("BREAKING CHANGE: {}", desc));
+            }
+        }
+        if let (Some(issue), IssueStyle::Footer) = (issue, self.issue_style) {
+            footers.push(format!(
+                "{} {}",
+                self.footer_keyword,
+                issue_reference(issue)
+            ));
+        }
+        footers.extend(self.trailers.iter().cloned());
+        if !footers.is_empty() {
+            msg.push_str("\n\n");
+            msg.push_str(&footers.join("\n"));
+        }
+        msg
+    }
+}
+
+/// `body` split into its free-form paragraphs and its footer paragraph, if the last
+/// paragraph consists only of footers (trailers, `BREAKING CHANGE` notes, `Closes #7`)
+fn split_footer(body: &str) -> (&str, Option<&str>) {
+    let body = body.trim();
+    let (prose, last) = match body.rsplit_once("\n\n") {
+        Some((prose, last)) => (prose.trim_end(), last),
+        None => ("", body),
+    };
+    if last.is_empty() || !last.lines().all(is_footer) {
+        return (body, None);
+    }
+    (prose, Some(last))
+}
+
+fn is_token(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_breaking(line: &str) -> bool {
+    line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+}
+
+fn is_trailer(line: &str) -> bool {
+    !is_breaking(line)
+        && line
+            .split_once(": ")
+            .is_some_and(|(token, value)| is_token(token) && !value.trim().is_empty())
+}
+
+fn is_footer(line: &str) -> bool {
+    is_breaking(line) || is_trailer(line) || line.split_once(" #").is_some_and(|(t, _)| is_token(t))
+}
+
+/// Git trailers (`Token: value`) in the footer paragraph of a commit body.
+///
+/// `BREAKING CHANGE` notes and issue footers such as `Closes #7` count as part of
+/// the footer but are not returned, [`CommitMessage`] renders those itself.
+pub fn trailers(body: &str) -> Vec<String> {
+    split_footer(body)
+        .1
+        .map(|footer| {
+            footer
+                .lines()
+                .filter(|line| is_trailer(line))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The paragraphs of a commit body before its footer, see [`trailers`]
+pub fn body_without_footer(body: &str) -> &str {
+    split_footer(body).0
+}
+
+/// Arguments for `git commit` that use `message` instead of opening an editor
+pub fn commit_args(
+    base_args: Vec<String>,
+    message: String,
+    extra_args: Vec<String>,
+) -> Vec<String> {
+    let mut args = vec!["commit".to_string()];
+    args.extend(base_args);
+    args.push("-m".to_string());
+    args.push(message);
+    args.extend(extra_args);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feat() -> CommitTypeConfig {
+        CommitTypeConfig::new("feat".into(), "✨".into(), "A new feature".into())
+    }
+
+    #[test]
+    fn test_render_with_issue_and_emoji() {
+        let commit_type = feat();
+        let msg = CommitMessage::new(&commit_type, Some("123".into()), "add filters".into(), true);
+        assert_eq!(msg.render(), "feat(#123): ✨ add filters");
+    }
+
+    #[test]
+    fn test_render_without_issue_or_emoji() {
+        let commit_type = feat();
+        let msg = CommitMessage::new(&commit_type, Some(" ".into()), "add filters".into(), false);
+        assert_eq!(msg.render(), "feat: add filters");
+    }
+
+    #[test]
+    fn test_render_issue_styles() {
+        let commit_type = feat();
+        let msg = |issue: &str, style| {
+            CommitMessage::new(
+                &commit_type,
+                Some(issue.into()),
+                "add filters".into(),
+                false,
+            )
+            .with_issue_config(&IssueConfig {
+                style,
+                ..IssueConfig::default()
+            })
+            .render()
+        };
+        assert_eq!(msg("PROJ-7", IssueStyle::Hash), "feat(PROJ-7): add filters");
+        assert_eq!(msg("#7", IssueStyle::Key), "feat(7): add filters");
+        assert_eq!(
+            msg("7", IssueStyle::Footer),
+            "feat: add filters\n\nCloses #7"
+        );
+    }
+
+    #[test]
+    fn test_render_breaking_change() {
+        let commit_type = feat();
+        let msg = |breaking: Option<&str>| {
+            CommitMessage::new(
+                &commit_type,
+                Some("7".into()),
+                "drop v1 config".into(),
+                false,
+            )
+            .with_issue_config(&IssueConfig {
+                style: IssueStyle::Footer,
+                ..IssueConfig::default()
+            })
+            .with_breaking(breaking.map(str::to_string))
+            .render()
+        };
+        assert_eq!(msg(Some("")), "feat!: drop v1 config\n\nCloses #7");
+        assert_eq!(
+            msg(Some("config v1 is no longer read")),
+            "feat!: drop v1 config\n\nBREAKING CHANGE: config v1 is no longer read\nCloses #7"
+        );
+        assert_eq!(
+            CommitMessage::new(&commit_type, Some("1".into()), "x".into(), true)
+                .with_breaking(Some(String::new()))
+                .render(),
+            "feat(#1)!: ✨ x"
+        );
+    }
+
+    #[test]
+    fn test_trailers() {
+        let body = "Details here.\n\nCo-authored-by: Ann <ann@example.com>\nSigned-off-by: Bo <bo@example.com>";
+        assert_eq!(
+            trailers(body),
+            [
+                "Co-authored-by: Ann <ann@example.com>",
+                "Signed-off-by: Bo <bo@example.com>"
+            ]
+        );
+        assert!(trailers("Just a body: with a colon\nand more text").is_empty());
+        assert_eq!(
+            trailers("BREAKING CHANGE: gone\nCloses #7\nCo-authored-by: Ann <a@b.c>"),
+            ["Co-authored-by: Ann <a@b.c>"]
+        );
+
+        let commit_type = feat();
+        let msg = CommitMessage::new(&commit_type, None, "x".into(), false)
+            .with_trailers(trailers(body))
+            .render();
+        assert!(msg.ends_with(
+            "x\n\nCo-authored-by: Ann <ann@example.com>\nSigned-off-by: Bo <bo@example.com>"
+        ));
+    }
+
+    #[test]
+    fn test_body_kept_without_footer() {
+        let body = "Why it changed.\n\nMore details.\n\nCloses #7\nCo-authored-by: Ann <a@b.c>";
+        assert_eq!(
+            body_without_footer(body),
+            "Why it changed.\n\nMore details."
+        );
+        assert_eq!(body_without_footer("Closes #7"), "");
+        assert_eq!(body_without_footer("Just prose"), "Just prose");
+
+        let commit_type = feat();
+        let msg = CommitMessage::new(&commit_type, Some("#7".into()), "x".into(), false)
+            .with_issue_config(&IssueConfig {
+                style: IssueStyle::Footer,
+                ..Default::default()
+            })
+            .with_body(body_without_footer(body))
+            .with_trailers(trailers(body))
+            .render();
+        assert_eq!(
+            msg,
+            "feat: x\n\nWhy it changed.\n\nMore details.\n\nCloses #7\nCo-authored-by: Ann <a@b.c>"
+        );
+    }
+
+    #[test]
+    fn test_commit_args_order() {
+        let args = commit_args(
+            vec!["--all".into()],
+            "feat: x".into(),
+            vec!["--no-verify".into()],
+        );
+        assert_eq!(args, ["commit", "--all", "-m", "feat: x", "--no-verify"]);
+    }
+}
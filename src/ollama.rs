@@ -0,0 +1,206 @@
+use crate::app_config::LLMConfig;
+use crate::i18n::{fill, tr};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use reqwest::Client;
+use serde::Deserialize;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+
+pub const DEFAULT_URL: &str = "http://localhost:11434";
+/// Pulled when `model` is unset and nothing is installed yet
+pub const DEFAULT_MODEL: &str = "llama3.2";
+
+/// Model checked or pulled by `prepare`, so it runs once per process
+static PREPARED_MODEL: OnceLock<String> = OnceLock::new();
+
+/// Server root for the configured `url`, which may also end in `/api` or `/v1`
+pub fn base_url(url: Option<&str>) -> String {
+    let url = url
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_URL)
+        .trim_end_matches('/');
+    url.strip_suffix("/api")
+        .or_else(|| url.strip_suffix("/v1"))
+        .unwrap_or(url)
+        .to_string()
+}
+
+/// A model in the local Ollama library
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalModel {
+    pub name: String,
+    /// Bytes on disk
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+struct Tags {
+    #[serde(default)]
+    models: Vec<LocalModel>,
+}
+
+/// One line of the `/api/pull` progress stream
+#[derive(Deserialize)]
+struct PullStatus {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    total: u64,
+    #[serde(default)]
+    completed: u64,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Installed models, newest first as Ollama lists them
+pub async fn list_models(base: &str) -> anyhow::Result<Vec<LocalModel>> {
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()?;
+    let resp = client
+        .get(format!("{}/api/tags", base))
+        .send()
+        .await
+        .map_err(|_| anyhow::anyhow!(fill(tr().ollama_unreachable, &[&base])))?;
+    Ok(resp.error_for_status()?.json::<Tags>().await?.models)
+}
+
+/// Whether `installed` is the model named `wanted`; a name without a tag means `:latest`
+pub fn same_model(installed: &str, wanted: &str) -> bool {
+    installed == wanted || (!wanted.contains(':') && installed == format!("{}:latest", wanted))
+}
+
+/// Embedding models such as `nomic-embed-text` are listed too but cannot chat
+pub fn is_embedding_model(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("embed") || name.starts_with("bge-") || name.contains("minilm")
+}
+
+/// `configured`, else `DEFAULT_MODEL` when it is installed, else the first installed
+/// chat model, else `DEFAULT_MODEL`
+pub fn pick_model<'a>(
+    installed: impl IntoIterator<Item = &'a str>,
+    configured: Option<&str>,
+) -> String {
+    if let Some(configured) = configured {
+        return configured.to_string();
+    }
+    let installed: Vec<&str> = installed.into_iter().collect();
+    installed
+        .iter()
+        .find(|name| same_model(name, DEFAULT_MODEL))
+        .or_else(|| installed.iter().find(|name| !is_embedding_model(name)))
+        .copied()
+        .unwrap_or(DEFAULT_MODEL)
+        .to_string()
+}
+
+/// Download `model`, showing Ollama's progress on stderr
+pub async fn pull(base: &str, model: &str) -> anyhow::Result<()> {
+    let mut resp = Client::new()
+        .post(format!("{}/api/pull", base))
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await?
+        .error_for_status()?;
+    let mut buffer = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let Ok(status) = serde_json::from_slice::<PullStatus>(&line) else {
+                continue;
+            };
+            if let Some(error) = status.error {
+                eprintln!();
+                anyhow::bail!("ollama pull {}: {}", model, error);
+            }
+            match (status.completed * 100).checked_div(status.total) {
+                Some(percent) => eprint!("\r\x1b[2K{} {}%", status.status, percent),
+                None => eprint!("\r\x1b[2K{}", status.status),
+            }
+            std::io::stderr().flush()?;
+        }
+    }
+    eprintln!();
+    Ok(())
+}
+
+/// `llm_config` with the server root as `url` and an installed model.
+///
+/// A missing model is pulled after asking, or reported with the command to pull it
+/// when there is no terminal to ask on.
+pub async fn prepare(llm_config: &LLMConfig) -> anyhow::Result<LLMConfig> {
+    let base = base_url(llm_config.url.as_deref());
+    let model = match PREPARED_MODEL.get() {
+        Some(model) => model.clone(),
+        None => {
+            let installed = list_models(&base).await?;
+            let model = pick_model(
+                installed.iter().map(|m| m.name.as_str()),
+                llm_config.model.as_deref(),
+            );
+            if !installed.iter().any(|m| same_model(&m.name, &model)) {
+                let interactive = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+                let agreed = interactive
+                    && Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt(fill(tr().ollama_pull_prompt, &[&model]))
+                        .default(true)
+                        .interact()?;
+                if !agreed {
+                    anyhow::bail!(fill(tr().ollama_missing_model, &[&model, &model]));
+                }
+                pull(&base, &model).await?;
+            }
+            PREPARED_MODEL.get_or_init(|| model).clone()
+        }
+    };
+    let mut prepared = llm_config.clone();
+    prepared.url = Some(base);
+    prepared.model = Some(model);
+    Ok(prepared)
+}
+
+/// Size as Ollama shows it, e.g. `4.7 GB`
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1_000_000 => format!("{} KB", bytes / 1000),
+        1_000_000..1_000_000_000 => format!("{} MB", bytes / 1_000_000),
+        _ => format!("{:.1} GB", bytes as f64 / 1e9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(base_url(None), DEFAULT_URL);
+        assert_eq!(base_url(Some(" ")), DEFAULT_URL);
+        assert_eq!(base_url(Some("http://gpu:11434/")), "http://gpu:11434");
+        assert_eq!(base_url(Some("http://gpu:11434/api")), "http://gpu:11434");
+        assert_eq!(base_url(Some("http://gpu:11434/v1/")), "http://gpu:11434");
+    }
+
+    #[test]
+    fn test_pick_model() {
+        let pick = |list: &[&str], configured| pick_model(list.iter().copied(), configured);
+        assert_eq!(
+            pick(&["qwen2.5-coder:7b", "llama3.2:latest"], None),
+            "llama3.2:latest"
+        );
+        assert_eq!(
+            pick(&["nomic-embed-text:latest", "qwen2.5-coder:7b"], None),
+            "qwen2.5-coder:7b"
+        );
+        assert_eq!(pick(&["nomic-embed-text:latest"], None), DEFAULT_MODEL);
+        assert_eq!(pick(&[], None), DEFAULT_MODEL);
+        assert_eq!(pick(&["llama3.2:latest"], Some("mistral")), "mistral");
+        assert!(same_model("llama3.2:latest", "llama3.2"));
+        assert!(!same_model("llama3.2:1b", "llama3.2"));
+        assert_eq!(format_size(4_700_000_000), "4.7 GB");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::app_config::{LLMConfig, LLMProvider};
+use crate::commit_types::{PrivacyPolicy, RemoteAction};
+use crate::diff::{build_globset, FileDiff, StagedDiff};
+use crate::i18n::{fill, tr};
+use globset::GlobSet;
+
+/// Apply the repo privacy policy before any prompt is built.
+///
+/// Files matching `exclude` are dropped from the diff. When `local_only` files are
+/// staged and `llm_config` points at a remote provider, this either refuses or
+/// rewrites `llm_config` to the configured local provider.
+pub fn enforce(
+    diff: &mut StagedDiff,
+    policy: &PrivacyPolicy,
+    llm_config: &mut LLMConfig,
+) -> anyhow::Result<()> {
+    let exclude = build_globset(&policy.exclude)?;
+    let before = diff.files.len();
+    diff.files.retain(|f| !matches(&exclude, f));
+    let dropped = before - diff.files.len();
+    if dropped > 0 {
+        eprintln!("{}", fill(tr().excluded_files, &[&dropped]));
+    }
+
+    if llm_config.is_local() {
+        return Ok(());
+    }
+    let local_only = build_globset(&policy.local_only)?;
+    let protected: Vec<&str> = diff
+        .files
+        .iter()
+        .filter(|f| matches(&local_only, f))
+        .map(|f| f.path.as_str())
+        .collect();
+    if protected.is_empty() {
+        return Ok(());
+    }
+
+    match policy.on_remote {
+        RemoteAction::Refuse => anyhow::bail!(fill(
+            tr().refuse_remote,
+            &[&format!("{:?}", llm_config.provider), &protected.join(", ")]
+        )),
+        RemoteAction::UseLocal => {
+            let local = LLMConfig::new(
+                LLMProvider::Ollama,
+                llm_config.enable,
+                None,
+                policy.local_url.clone(),
+                policy.local_model.clone(),
+            );
+            // `local_url` comes from the repo, so it may point anywhere
+            if !local.is_local() {
+                anyhow::bail!(fill(
+                    tr().local_url_remote,
+                    &[&policy.local_url.as_deref().unwrap_or_default()]
+                ));
+            }
+            eprintln!("{}", fill(tr().switch_local, &[&protected.join(", ")]));
+            *llm_config = local;
+            Ok(())
+        }
+    }
+}
+
+/// Whether `file` or, for a rename or copy, its old path is in `set`
+fn matches(set: &GlobSet, file: &FileDiff) -> bool {
+    set.is_match(&file.path) || file.old_path().is_some_and(|path| set.is_match(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staged() -> StagedDiff {
+        StagedDiff::parse(
+            "\
+diff --git a/crypto/keys.rs b/crypto/keys.rs
+@@ -1 +1 @@
+-a
++b
+diff --git a/secrets/prod.toml b/secrets/prod.toml
+@@ -1 +1 @@
+-a
++b
+diff --git a/src/main.rs b/src/main.rs
+@@ -1 +1 @@
+-a
++b
+",
+        )
+    }
+
+    fn policy(on_remote: RemoteAction) -> PrivacyPolicy {
+        PrivacyPolicy {
+            exclude: vec!["secrets/**".into()],
+            local_only: vec!["crypto/**".into()],
+            on_remote,
+            local_model: Some("llama3.1".into()),
+            local_url: None,
+        }
+    }
+
+    fn remote() -> LLMConfig {
+        LLMConfig::new(LLMProvider::OpenAI, true, Some("sk".into()), None, None)
+    }
+
+    #[test]
+    fn test_excludes_and_refuses_remote() {
+        let mut diff = staged();
+        let mut llm_config = remote();
+        let err = enforce(&mut diff, &policy(RemoteAction::Refuse), &mut llm_config).unwrap_err();
+        assert!(err.to_string().contains("crypto/keys.rs"));
+        assert!(diff.files.iter().all(|f| f.path != "secrets/prod.toml"));
+    }
+
+    #[test]
+    fn test_switches_to_local_provider() {
+        let mut diff = staged();
+        let mut llm_config = remote();
+        enforce(&mut diff, &policy(RemoteAction::UseLocal), &mut llm_config).unwrap();
+        assert_eq!(llm_config.provider, LLMProvider::Ollama);
+        assert_eq!(llm_config.model.as_deref(), Some("llama3.1"));
+        assert_eq!(diff.files.len(), 2);
+    }
+
+    #[test]
+    fn test_remote_ollama_is_not_local() {
+        let mut diff = staged();
+        let mut llm_config = LLMConfig::new(
+            LLMProvider::Ollama,
+            true,
+            None,
+            Some("http://gpu.corp:11434".into()),
+            None,
+        );
+        assert!(enforce(&mut diff, &policy(RemoteAction::Refuse), &mut llm_config).is_err());
+
+        let mut policy = policy(RemoteAction::UseLocal);
+        policy.local_url = Some("http://gpu.corp:11434".into());
+        let mut llm_config = remote();
+        assert!(enforce(&mut diff, &policy, &mut llm_config).is_err());
+        assert_eq!(llm_config.provider, LLMProvider::OpenAI);
+    }
+
+    #[test]
+    fn test_matches_old_path_of_renames() {
+        let mut diff = StagedDiff::parse(
+            "\
+diff --git a/secrets/prod.toml b/config/prod.toml
+similarity index 100%
+rename from secrets/prod.toml
+rename to config/prod.toml
+diff --git a/crypto/keys.rs b/src/keys.rs
+similarity index 90%
+rename from crypto/keys.rs
+rename to src/keys.rs
+@@ -1 +1 @@
+-a
++b
+",
+        );
+        let mut llm_config = remote();
+        let err = enforce(&mut diff, &policy(RemoteAction::Refuse), &mut llm_config).unwrap_err();
+        assert!(err.to_string().contains("src/keys.rs"));
+        assert_eq!(diff.files.len(), 1);
+    }
+
+    #[test]
+    fn test_local_provider_is_allowed() {
+        let mut diff = staged();
+        let mut llm_config = LLMConfig::new(
+            LLMProvider::CUSTOM,
+            true,
+            None,
+            Some("http://127.0.0.1:8080/v1/chat/completions".into()),
+            None,
+        );
+        enforce(&mut diff, &policy(RemoteAction::Refuse), &mut llm_config).unwrap();
+        assert_eq!(llm_config.provider, LLMProvider::CUSTOM);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::app_config::get_config_dir;
+use crate::git_utils::get_repo_root;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Per-repo template directory, relative to the repo root
+pub const REPO_PROMPT_DIR: &str = ".gcw/prompts";
+
+/// Built-in templates as (name, system, user)
+const BUILTIN: &[(&str, &str, &str)] = &[
+    (
+        "commit",
+        include_str!("prompts/commit.system.md"),
+        include_str!("prompts/commit.md"),
+    ),
+    (
+        "breaking",
+        include_str!("prompts/breaking.system.md"),
+        include_str!("prompts/breaking.md"),
+    ),
+    (
+        "changelog",
+        include_str!("prompts/changelog.system.md"),
+        include_str!("prompts/changelog.md"),
+    ),
+    (
+        "branch",
+        include_str!("prompts/branch.system.md"),
+        include_str!("prompts/branch.md"),
+    ),
+    (
+        "split",
+        include_str!("prompts/split.system.md"),
+        include_str!("prompts/split.md"),
+    ),
+    (
+        "pr-desc",
+        include_str!("prompts/pr-desc.system.md"),
+        include_str!("prompts/pr-desc.md"),
+    ),
+    (
+        "squash",
+        include_str!("prompts/squash.system.md"),
+        include_str!("prompts/squash.md"),
+    ),
+    (
+        "review",
+        include_str!("prompts/review.system.md"),
+        include_str!("prompts/review.md"),
+    ),
+    (
+        "explain",
+        include_str!("prompts/explain.system.md"),
+        include_str!("prompts/explain.md"),
+    ),
+];
+
+/// A system + user prompt pair before variables are filled in
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub system: String,
+    pub user: String,
+}
+
+/// A prompt ready to send
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    pub system: String,
+    pub user: String,
+}
+
+impl PromptTemplate {
+    /// Load template `name`, each part from the first of: `.gcw/prompts/`, the global
+    /// `prompts/` config directory, the built-in default.
+    ///
+    /// `<name>.md` holds the user prompt and `<name>.system.md` the system prompt.
+    pub fn load(name: &str) -> anyhow::Result<Self> {
+        let (_, system, user) = BUILTIN
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown prompt template: {}", name))?;
+        let dirs = template_dirs();
+        Ok(Self {
+            system: read_first(&dirs, &format!("{}.system.md", name))?
+                .unwrap_or_else(|| system.to_string()),
+            user: read_first(&dirs, &format!("{}.md", name))?.unwrap_or_else(|| user.to_string()),
+        })
+    }
+
+    pub fn render(&self, vars: &HashMap<&str, String>) -> RenderedPrompt {
+        RenderedPrompt {
+            system: render(&self.system, vars).trim().to_string(),
+            user: render(&self.user, vars).trim_end().to_string(),
+        }
+    }
+}
+
+fn template_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(root) = get_repo_root() {
+        dirs.push(root.join(REPO_PROMPT_DIR));
+    }
+    if let Ok(config_dir) = get_config_dir() {
+        dirs.push(config_dir.join("prompts"));
+    }
+    dirs
+}
+
+fn read_first(dirs: &[PathBuf], file: &str) -> anyhow::Result<Option<String>> {
+    for dir in dirs {
+        let path = dir.join(file);
+        if path.is_file() {
+            return Ok(Some(std::fs::read_to_string(path)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Fill `{{name}}` variables and `{{#name}}...{{/name}}` sections.
+///
+/// A section is kept only when its variable is non-empty, and a newline right after
+/// a section tag is dropped so tags can sit on their own lines. Unknown variables are
+/// left untouched so mistakes show up in `gcw prompt show`.
+pub fn render(template: &str, vars: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(name) = tag.strip_prefix('#') {
+            let close = format!("{{{{/{}}}}}", name);
+            let (inner, tail) = match rest.find(&close) {
+                Some(idx) => (&rest[..idx], &rest[idx + close.len()..]),
+                None => (rest, ""),
+            };
+            if vars.get(name).is_some_and(|v| !v.trim().is_empty()) {
+                out.push_str(&render(inner.strip_prefix('\n').unwrap_or(inner), vars));
+            }
+            rest = tail.strip_prefix('\n').unwrap_or(tail);
+        } else {
+            match vars.get(tag) {
+                Some(value) => out.push_str(value),
+                None => {
+                    out.push_str("{{");
+                    out.push_str(&after[..end]);
+                    out.push_str("}}");
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&'static str, &str)]) -> HashMap<&'static str, String> {
+        pairs.iter().map(|(k, v)| (*k, v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_variables_and_sections() {
+        let template = "type={{type}}\n{{#branch}}\nbranch: {{ branch }}\n{{/branch}}\n{{#history}}\nhistory\n{{/history}}\n{{unknown}}";
+        let rendered = render(template, &vars(&[("type", "feat"), ("branch", "main")]));
+        assert_eq!(rendered, "type=feat\nbranch: main\n{{unknown}}");
+    }
+
+    #[test]
+    fn test_builtin_commit_template() {
+        let (_, system, user) = BUILTIN[0];
+        let template = PromptTemplate {
+            system: system.into(),
+            user: user.into(),
+        };
+        let prompt = template.render(&vars(&[
+            ("type", "fix"),
+            ("type_desc", "A bug fix"),
+            ("language", "English"),
+            ("diff", "+a"),
+            ("branch", ""),
+            ("history", ""),
+            ("notes", ""),
+        ]));
+        assert!(prompt.user.contains("[fix: A bug fix]"));
+        assert!(prompt
+            .user
+            .ends_with("[docs] → Clarify multi-factor auth setup steps\n\nDiff to analyze:\n+a"));
+        assert!(!prompt.system.is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::changelog::ConventionalCommit;
+use regex::Regex;
+use semver::{Prerelease, Version};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// Largest bump required by `commits`: breaking → major, `feat` → minor,
+/// `fix`/`perf` → patch. `None` when nothing warrants a release.
+pub fn bump_for(commits: &[ConventionalCommit]) -> Option<Bump> {
+    commits
+        .iter()
+        .filter_map(|c| {
+            if c.breaking.is_some() {
+                return Some(Bump::Major);
+            }
+            match c.commit_type.as_deref() {
+                Some("feat") => Some(Bump::Minor),
+                Some("fix") | Some("perf") => Some(Bump::Patch),
+                _ => None,
+            }
+        })
+        .max()
+}
+
+/// Apply `bump` to `current`, optionally as a pre-release such as `rc`.
+///
+/// The base of a pre-release counts as already bumped when it covers `bump`
+/// (`1.3.0-rc.1` is a minor bump), so a pre-release of the same identifier is
+/// continued (`1.3.0-rc.1` → `1.3.0-rc.2`) unless `bump` is larger
+/// (`1.3.0-rc.1` with a breaking change → `2.0.0-rc.1`). Releasing a
+/// pre-release drops its suffix (`1.3.0-rc.2` → `1.3.0`).
+pub fn next_version(current: &Version, bump: Bump, pre: Option<&str>) -> anyhow::Result<Version> {
+    let base = Version::new(current.major, current.minor, current.patch);
+    let covered = !current.pre.is_empty()
+        && match bump {
+            Bump::Major => base.minor == 0 && base.patch == 0,
+            Bump::Minor => base.patch == 0,
+            Bump::Patch => true,
+        };
+    let mut next = match bump {
+        _ if covered => base,
+        Bump::Major => Version::new(base.major + 1, 0, 0),
+        Bump::Minor => Version::new(base.major, base.minor + 1, 0),
+        Bump::Patch => Version::new(base.major, base.minor, base.patch + 1),
+    };
+    if let Some(pre) = pre {
+        let counter = Some(current.pre.as_str())
+            .filter(|_| covered)
+            .and_then(|current| current.strip_prefix(pre))
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|n| n.parse::<u64>().ok());
+        let n = counter.map_or(1, |n| n + 1);
+        next.pre = Prerelease::new(&format!("{}.{}", pre, n))?;
+    }
+    Ok(next)
+}
+
+/// Version manifest at the repo root whose version can be bumped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Manifest {
+    Cargo(PathBuf),
+    Npm(PathBuf),
+    Python(PathBuf),
+}
+
+impl Manifest {
+    /// Manifests present in `root`
+    pub fn detect(root: &Path) -> Vec<Manifest> {
+        [
+            Manifest::Cargo(root.join("Cargo.toml")),
+            Manifest::Npm(root.join("package.json")),
+            Manifest::Python(root.join("pyproject.toml")),
+        ]
+        .into_iter()
+        .filter(|m| m.path().is_file())
+        .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Manifest::Cargo(path) | Manifest::Npm(path) | Manifest::Python(path) => path,
+        }
+    }
+
+    pub fn read_version(&self) -> anyhow::Result<Option<String>> {
+        let content = std::fs::read_to_string(self.path())?;
+        Ok(self.find_version(&content).map(|(_, version)| version))
+    }
+
+    /// Byte range of the version string in `content`, and its value
+    fn find_version(&self, content: &str) -> Option<(std::ops::Range<usize>, String)> {
+        match self {
+            Manifest::Npm(_) => json_version(content),
+            Manifest::Cargo(_) => toml_version(content, &["package"]),
+            Manifest::Python(_) => toml_version(content, &["project", "tool.poetry"]),
+        }
+    }
+
+    /// Set the version, keeping the rest of the file as is. Returns false when the
+    /// manifest has no literal version (e.g. `version.workspace = true`).
+    pub fn write_version(&self, version: &Version) -> anyhow::Result<bool> {
+        let content = std::fs::read_to_string(self.path())?;
+        let Some((range, _)) = self.find_version(&content) else {
+            return Ok(false);
+        };
+        let mut updated = content.clone();
+        updated.replace_range(range, &version.to_string());
+        std::fs::write(self.path(), updated)?;
+        Ok(true)
+    }
+}
+
+/// The top-level `"version": "..."` of a JSON object, skipping nested objects such as
+/// `"engines"` or `"volta"` that may have their own
+fn json_version(content: &str) -> Option<(std::ops::Range<usize>, String)> {
+    let regex = Regex::new(r#"^"version"\s*:\s*"([^"]*)""#).unwrap();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in content.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            '"' => {
+                if depth == 1 {
+                    if let Some(m) = regex.captures(&content[i..]).and_then(|caps| caps.get(1)) {
+                        return Some((i + m.start()..i + m.end(), m.as_str().to_string()));
+                    }
+                }
+                in_string = true;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `version = "..."` directly under one of the `tables` in a TOML file
+fn toml_version(content: &str, tables: &[&str]) -> Option<(std::ops::Range<usize>, String)> {
+    let regex = Regex::new(r#"^\s*version\s*=\s*"([^"]*)""#).unwrap();
+    let mut in_table = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            let name = trimmed.trim_matches(|c| c == '[' || c == ']').trim();
+            in_table = tables.contains(&name);
+        } else if in_table {
+            if let Some(m) = regex.captures(line).and_then(|caps| caps.get(1)) {
+                return Some((offset + m.start()..offset + m.end(), m.as_str().to_string()));
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Point the package's own entry in `Cargo.lock` at `version`, if the lockfile exists
+pub fn update_cargo_lock(
+    root: &Path,
+    name: &str,
+    old: &str,
+    version: &Version,
+) -> anyhow::Result<bool> {
+    let path = root.join("Cargo.lock");
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(false);
+    };
+    let entry = format!("name = \"{}\"\nversion = \"{}\"", name, old);
+    if !content.contains(&entry) {
+        return Ok(false);
+    }
+    let updated = content.replacen(
+        &entry,
+        &format!("name = \"{}\"\nversion = \"{}\"", name, version),
+        1,
+    );
+    std::fs::write(path, updated)?;
+    Ok(true)
+}
+
+/// `name` from the `[package]` table of a Cargo manifest
+pub fn cargo_package_name(manifest: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(manifest).ok()?;
+    let regex = Regex::new(r#"(?m)^\s*name\s*=\s*"([^"]*)""#).unwrap();
+    let package = content.split("[package]").nth(1)?;
+    let package = package.split("\n[").next()?;
+    Some(regex.captures(package)?[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(commit_type: &str, breaking: bool) -> ConventionalCommit {
+        ConventionalCommit {
+            hash: "abc".into(),
+            commit_type: Some(commit_type.into()),
+            scope: None,
+            subject: "x".into(),
+            breaking: breaking.then(String::new),
+            issues: Vec::new(),
+        }
+    }
+
+    fn next(current: &str, bump: Bump, pre: Option<&str>) -> String {
+        next_version(&Version::parse(current).unwrap(), bump, pre)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_bump_for() {
+        assert_eq!(bump_for(&[commit("docs", false)]), None);
+        assert_eq!(
+            bump_for(&[commit("fix", false), commit("docs", false)]),
+            Some(Bump::Patch)
+        );
+        assert_eq!(
+            bump_for(&[commit("fix", false), commit("feat", false)]),
+            Some(Bump::Minor)
+        );
+        assert_eq!(
+            bump_for(&[commit("feat", false), commit("chore", true)]),
+            Some(Bump::Major)
+        );
+    }
+
+    #[test]
+    fn test_next_version() {
+        assert_eq!(next("1.2.3", Bump::Patch, None), "1.2.4");
+        assert_eq!(next("1.2.3", Bump::Minor, None), "1.3.0");
+        assert_eq!(next("1.2.3", Bump::Major, None), "2.0.0");
+        assert_eq!(next("1.2.3", Bump::Minor, Some("rc")), "1.3.0-rc.1");
+        assert_eq!(next("1.3.0-rc.1", Bump::Patch, Some("rc")), "1.3.0-rc.2");
+        assert_eq!(
+            next("1.3.0-rc.2", Bump::Patch, Some("beta")),
+            "1.3.0-beta.1"
+        );
+        assert_eq!(next("1.3.0-rc.2", Bump::Minor, None), "1.3.0");
+        assert_eq!(next("1.3.0-rc.1", Bump::Major, Some("rc")), "2.0.0-rc.1");
+        assert_eq!(next("1.3.0-rc.1", Bump::Major, None), "2.0.0");
+        assert_eq!(next("1.3.1-rc.1", Bump::Minor, Some("rc")), "1.4.0-rc.1");
+        assert_eq!(next("2.0.0-rc.1", Bump::Minor, Some("rc")), "2.0.0-rc.2");
+    }
+
+    #[test]
+    fn test_write_manifest_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo = dir.path().join("Cargo.toml");
+        std::fs::write(
+            &cargo,
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\nfoo = { version = \"1\" }\n",
+        )
+        .unwrap();
+        let pyproject = dir.path().join("pyproject.toml");
+        std::fs::write(
+            &pyproject,
+            "[build-system]\nrequires = []\n\n[project]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            "{\n  \"name\": \"demo\",\n  \"engines\": { \"version\": \">=18\" },\n  \"version\": \"0.1.0\"\n}\n",
+        )
+        .unwrap();
+
+        let version = Version::parse("0.2.0").unwrap();
+        let manifests = Manifest::detect(dir.path());
+        assert_eq!(manifests.len(), 3);
+        for manifest in &manifests {
+            assert_eq!(manifest.read_version().unwrap().as_deref(), Some("0.1.0"));
+            assert!(manifest.write_version(&version).unwrap());
+            assert_eq!(manifest.read_version().unwrap().as_deref(), Some("0.2.0"));
+        }
+        assert!(std::fs::read_to_string(&cargo)
+            .unwrap()
+            .ends_with("foo = { version = \"1\" }\n"));
+        assert_eq!(cargo_package_name(&cargo).as_deref(), Some("demo"));
+    }
+}
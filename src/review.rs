@@ -0,0 +1,119 @@
+use crate::commit_types::Severity;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// One problem reported by `gcw review`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(deserialize_with = "lenient_severity")]
+    pub severity: Severity,
+    /// `bug`, `debug`, `todo` or `tests`
+    #[serde(default)]
+    pub category: String,
+    pub message: String,
+}
+
+/// Severity names the model made up count as `Error` when they sound like one
+/// (`critical`, `high`, ...) and as `Warning` otherwise, so they are never
+/// ranked below the threshold that should block them
+fn lenient_severity<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Severity, D::Error> {
+    let name = String::deserialize(de)?;
+    Ok(
+        Severity::parse(&name).unwrap_or_else(|| match name.trim().to_lowercase().as_str() {
+            "critical" | "fatal" | "blocker" | "high" | "major" | "severe" => Severity::Error,
+            _ => Severity::Warning,
+        }),
+    )
+}
+
+/// Parse the JSON array in an LLM reply, ignoring any text or code fences around it
+pub fn parse_findings(reply: &str) -> anyhow::Result<Vec<Finding>> {
+    let start = reply.find('[');
+    let end = reply.rfind(']');
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => Ok(serde_json::from_str(&reply[start..=end])?),
+        _ => anyhow::bail!("Expected a JSON array of findings, got: {}", reply.trim()),
+    }
+}
+
+/// Findings grouped by file, each file's sorted by line:
+///
+/// ```text
+/// src/lib.rs
+///     12  error    [bug] index can be out of bounds
+/// ```
+pub fn report(findings: &[Finding]) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in findings {
+        by_file.entry(&finding.file).or_default().push(finding);
+    }
+    let mut out = String::new();
+    for (file, mut findings) in by_file {
+        findings.sort_by_key(|f| (f.line, std::cmp::Reverse(f.severity)));
+        out.push_str(file);
+        out.push('\n');
+        for finding in findings {
+            let line = finding.line.map_or("-".to_string(), |l| l.to_string());
+            let category = if finding.category.is_empty() {
+                String::new()
+            } else {
+                format!("[{}] ", finding.category)
+            };
+            out.push_str(&format!(
+                "  {:>5}  {:<8} {}{}\n",
+                line,
+                finding.severity.label(),
+                category,
+                finding.message.trim()
+            ));
+        }
+    }
+    out
+}
+
+/// Number of findings at or above `threshold`
+pub fn count_blocking(findings: &[Finding], threshold: Severity) -> usize {
+    findings.iter().filter(|f| f.severity >= threshold).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_report_findings() {
+        let findings = parse_findings(
+            r#"```json
+[
+  {"file": "src/b.rs", "line": 40, "severity": "warning", "category": "debug", "message": "Leftover dbg! call"},
+  {"file": "src/a.rs", "severity": "Info", "category": "tests", "message": "No test covers the new parser"},
+  {"file": "src/b.rs", "line": 7, "severity": "Error", "category": "bug", "message": "Index can be out of bounds"},
+  {"file": "src/b.rs", "line": 9, "severity": "critical", "message": "Unknown severity"},
+  {"file": "src/b.rs", "line": 12, "severity": "minor", "message": "Made-up severity"}
+]
+```"#,
+        )
+        .unwrap();
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[3].severity, Severity::Error);
+        assert_eq!(findings[4].severity, Severity::Warning);
+        assert_eq!(
+            report(&findings),
+            "\
+src/a.rs
+      -  info     [tests] No test covers the new parser
+src/b.rs
+      7  error    [bug] Index can be out of bounds
+      9  error    Unknown severity
+     12  warning  Made-up severity
+     40  warning  [debug] Leftover dbg! call
+"
+        );
+        assert_eq!(count_blocking(&findings, Severity::Warning), 4);
+        assert_eq!(count_blocking(&findings, Severity::Error), 2);
+        assert!(parse_findings("[]").unwrap().is_empty());
+    }
+}
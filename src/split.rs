@@ -0,0 +1,207 @@
+use crate::commit_types::Config;
+use crate::diff::{FileStatus, StagedDiff};
+use crate::git_utils::git_output;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// One commit of a split plan
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SplitGroup {
+    #[serde(rename = "type")]
+    pub commit_type: String,
+    pub message: String,
+    pub files: Vec<String>,
+}
+
+/// Parse the JSON array in an LLM reply, ignoring any text or code fences around it
+pub fn parse_plan(reply: &str) -> anyhow::Result<Vec<SplitGroup>> {
+    let start = reply.find('[');
+    let end = reply.rfind(']');
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => Ok(serde_json::from_str(&reply[start..=end])?),
+        _ => anyhow::bail!(
+            "Expected a JSON array of commit groups, got: {}",
+            reply.trim()
+        ),
+    }
+}
+
+/// Make the plan cover every staged file exactly once.
+///
+/// Unknown files are dropped, a file listed twice stays in its first group, files
+/// the plan missed (e.g. excluded from the prompt) go to the last group, and unknown
+/// types fall back to `chore` or the first configured type.
+pub fn normalize_plan(
+    groups: Vec<SplitGroup>,
+    diff: &StagedDiff,
+    config: &Config,
+) -> anyhow::Result<Vec<SplitGroup>> {
+    let staged: Vec<&str> = diff.files.iter().map(|f| f.path.as_str()).collect();
+    let fallback_type = config
+        .types
+        .iter()
+        .find(|t| t.name == "chore")
+        .or_else(|| config.types.first())
+        .map(|t| t.name.clone())
+        .unwrap_or_else(|| "chore".into());
+
+    let mut seen = HashSet::new();
+    let mut plan: Vec<SplitGroup> = groups
+        .into_iter()
+        .map(|mut group| {
+            group
+                .files
+                .retain(|f| staged.contains(&f.as_str()) && seen.insert(f.clone()));
+            if !config.types.iter().any(|t| t.name == group.commit_type) {
+                group.commit_type = fallback_type.clone();
+            }
+            group.message = group.message.trim().to_string();
+            group
+        })
+        .filter(|group| !group.files.is_empty())
+        .collect();
+
+    let missing: Vec<String> = staged
+        .iter()
+        .filter(|f| !seen.contains(**f))
+        .map(|f| f.to_string())
+        .collect();
+    if !missing.is_empty() {
+        match plan.last_mut() {
+            Some(last) => last.files.extend(missing),
+            None => anyhow::bail!("The split plan does not contain any staged file"),
+        }
+    }
+    Ok(plan)
+}
+
+/// Paths to stage for `files`, including the old side of renames
+fn pathspecs(files: &[String], diff: &StagedDiff) -> Vec<String> {
+    let mut paths = Vec::new();
+    for file in files {
+        paths.push(file.clone());
+        let renamed_from = diff.files.iter().find_map(|f| match &f.status {
+            FileStatus::Renamed { from } if &f.path == file => Some(from.clone()),
+            _ => None,
+        });
+        paths.extend(renamed_from);
+    }
+    paths
+}
+
+/// Commit each `(message, files)` group in order, staging the files exactly as they
+/// are staged now. The working tree is never touched.
+///
+/// On failure HEAD and the index are reset to where they were, so the staged
+/// changes are back as they were before the split.
+pub fn commit_groups(
+    groups: &[(String, Vec<String>)],
+    diff: &StagedDiff,
+    extra_args: &[String],
+) -> anyhow::Result<()> {
+    let head = git_output(&["rev-parse", "--verify", "HEAD"])
+        .map_err(|_| anyhow::anyhow!("Splitting needs an existing commit to start from"))?;
+    let staged_tree = git_output(&["write-tree"])?;
+    git_output(&["reset", "--quiet"])?;
+
+    let result = groups.iter().try_for_each(|(message, files)| {
+        let mut restore = vec![
+            "restore".to_string(),
+            "--staged".to_string(),
+            format!("--source={}", staged_tree),
+            "--".to_string(),
+        ];
+        restore.extend(pathspecs(files, diff));
+        git_output(&restore.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+        let mut commit = vec!["commit", "--quiet", "-m", message];
+        commit.extend(extra_args.iter().map(String::as_str));
+        git_output(&commit).map(|_| ())
+    });
+
+    if let Err(err) = result {
+        git_output(&["reset", "--quiet", "--soft", &head])?;
+        git_output(&["read-tree", &staged_tree])?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit_types::{CommitTypeConfig, EmojiConfig};
+
+    fn diff() -> StagedDiff {
+        StagedDiff::parse(
+            "\
+diff --git a/src/a.rs b/src/a.rs
+@@ -1 +1 @@
+-a
++b
+diff --git a/README.md b/README.md
+@@ -1 +1 @@
+-x
++y
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+",
+        )
+    }
+
+    fn config() -> Config {
+        Config::new(
+            EmojiConfig::new(false),
+            vec![
+                CommitTypeConfig::new("feat".into(), "".into(), "".into()),
+                CommitTypeConfig::new("docs".into(), "".into(), "".into()),
+                CommitTypeConfig::new("chore".into(), "".into(), "".into()),
+            ],
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn test_parse_plan_in_code_fence() {
+        let plan = parse_plan(
+            "Here you go:\n```json\n[{\"type\": \"docs\", \"message\": \"explain\", \"files\": [\"README.md\"]}]\n```",
+        )
+        .unwrap();
+        assert_eq!(plan[0].commit_type, "docs");
+        assert_eq!(plan[0].files, ["README.md"]);
+        assert!(parse_plan("no plan").is_err());
+    }
+
+    #[test]
+    fn test_normalize_plan_covers_every_file_once() {
+        let groups = vec![
+            SplitGroup {
+                commit_type: "feat".into(),
+                message: " add b ".into(),
+                files: vec!["src/a.rs".into(), "missing.rs".into()],
+            },
+            SplitGroup {
+                commit_type: "wip".into(),
+                message: "docs".into(),
+                files: vec!["README.md".into(), "src/a.rs".into()],
+            },
+        ];
+        let plan = normalize_plan(groups, &diff(), &config()).unwrap();
+        assert_eq!(plan[0].files, ["src/a.rs"]);
+        assert_eq!(plan[0].message, "add b");
+        assert_eq!(plan[1].commit_type, "chore");
+        assert_eq!(plan[1].files, ["README.md", "new.rs"]);
+        assert_eq!(
+            pathspecs(&plan[1].files, &diff()),
+            ["README.md", "new.rs", "old.rs"]
+        );
+    }
+}
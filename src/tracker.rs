@@ -0,0 +1,380 @@
+use crate::app_config::{get_config_dir, TrackerConfig, TrackerKind};
+use crate::git_utils::git_output;
+use crate::i18n::{fill, tr};
+use reqwest::{Client, RequestBuilder};
+use rllm::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Looks up issue titles so they can be added to the prompt
+#[async_trait]
+pub trait IssueTracker: Send + Sync {
+    /// Stable identifier used as the cache namespace
+    fn cache_key(&self) -> String;
+
+    /// Title of `id`, `None` when the issue does not exist
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>>;
+}
+
+fn http_client() -> anyhow::Result<Client> {
+    Ok(Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .user_agent("gcw")
+        .build()?)
+}
+
+async fn get_json(request: RequestBuilder) -> anyhow::Result<Option<serde_json::Value>> {
+    let resp = request.send().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(resp.error_for_status()?.json().await?))
+}
+
+pub struct GitHubTracker {
+    pub base_url: String,
+    /// `owner/name`
+    pub repo: String,
+    pub token: Option<String>,
+}
+
+#[async_trait]
+impl IssueTracker for GitHubTracker {
+    fn cache_key(&self) -> String {
+        format!("github:{}/{}", self.base_url, self.repo)
+    }
+
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>> {
+        let url = format!(
+            "{}/repos/{}/issues/{}",
+            self.base_url.trim_end_matches('/'),
+            self.repo,
+            id.trim_start_matches('#')
+        );
+        let mut request = http_client()?
+            .get(url)
+            .header("Accept", "application/vnd.github+json");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let json = get_json(request).await?;
+        Ok(json.and_then(|v| v["title"].as_str().map(str::to_string)))
+    }
+}
+
+pub struct GitLabTracker {
+    pub base_url: String,
+    /// `group/project` path or numeric project ID
+    pub project: String,
+    pub token: Option<String>,
+}
+
+#[async_trait]
+impl IssueTracker for GitLabTracker {
+    fn cache_key(&self) -> String {
+        format!("gitlab:{}/{}", self.base_url, self.project)
+    }
+
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>> {
+        let url = format!(
+            "{}/api/v4/projects/{}/issues/{}",
+            self.base_url.trim_end_matches('/'),
+            self.project.replace('/', "%2F"),
+            id.trim_start_matches('#')
+        );
+        let mut request = http_client()?.get(url);
+        if let Some(token) = &self.token {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+        let json = get_json(request).await?;
+        Ok(json.and_then(|v| v["title"].as_str().map(str::to_string)))
+    }
+}
+
+pub struct JiraTracker {
+    pub base_url: String,
+    pub token: Option<String>,
+    /// Jira Cloud uses basic auth with the account email; without it the token is a bearer PAT
+    pub user: Option<String>,
+}
+
+#[async_trait]
+impl IssueTracker for JiraTracker {
+    fn cache_key(&self) -> String {
+        format!("jira:{}", self.base_url)
+    }
+
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>> {
+        let url = format!(
+            "{}/rest/api/2/issue/{}?fields=summary",
+            self.base_url.trim_end_matches('/'),
+            id
+        );
+        let mut request = http_client()?.get(url);
+        request = match (&self.user, &self.token) {
+            (Some(user), token) => request.basic_auth(user, token.as_ref()),
+            (None, Some(token)) => request.bearer_auth(token),
+            (None, None) => request,
+        };
+        let json = get_json(request).await?;
+        Ok(json.and_then(|v| v["fields"]["summary"].as_str().map(str::to_string)))
+    }
+}
+
+/// Offline tracker backed by a JSON object of `{"<id>": "<title>"}`
+pub struct LocalTracker {
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl IssueTracker for LocalTracker {
+    fn cache_key(&self) -> String {
+        format!("local:{}", self.path.display())
+    }
+
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>> {
+        let content = std::fs::read_to_string(&self.path)?;
+        let issues: HashMap<String, String> = serde_json::from_str(&content)?;
+        Ok(issues
+            .get(id)
+            .or_else(|| issues.get(id.trim_start_matches('#')))
+            .cloned())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    title: Option<String>,
+    fetched_at: u64,
+}
+
+/// Wraps a tracker with a JSON cache on disk, so repeated lookups work offline
+pub struct CachedTracker {
+    inner: Box<dyn IssueTracker>,
+    path: PathBuf,
+    ttl_secs: u64,
+}
+
+impl CachedTracker {
+    pub fn new(inner: Box<dyn IssueTracker>, path: PathBuf, ttl_secs: u64) -> Self {
+        Self {
+            inner,
+            path,
+            ttl_secs,
+        }
+    }
+
+    fn read_cache(&self) -> HashMap<String, CacheEntry> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_cache(&self, cache: &HashMap<String, CacheEntry>) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(cache)?)?;
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl IssueTracker for CachedTracker {
+    fn cache_key(&self) -> String {
+        self.inner.cache_key()
+    }
+
+    async fn issue_title(&self, id: &str) -> anyhow::Result<Option<String>> {
+        let key = format!("{}#{}", self.inner.cache_key(), id);
+        let mut cache = self.read_cache();
+        if let Some(entry) = cache.get(&key) {
+            if now_secs().saturating_sub(entry.fetched_at) < self.ttl_secs {
+                return Ok(entry.title.clone());
+            }
+        }
+
+        let title = match self.inner.issue_title(id).await {
+            Ok(title) => title,
+            // Offline: a stale entry is better than nothing
+            Err(err) => match cache.get(&key) {
+                Some(entry) => return Ok(entry.title.clone()),
+                None => return Err(err),
+            },
+        };
+        cache.insert(
+            key,
+            CacheEntry {
+                title: title.clone(),
+                fetched_at: now_secs(),
+            },
+        );
+        self.write_cache(&cache)?;
+        Ok(title)
+    }
+}
+
+/// `owner/name` from the `origin` remote, for GitHub and GitLab when `project` is unset
+fn project_from_origin() -> anyhow::Result<String> {
+    let url = git_output(&["remote", "get-url", "origin"])?;
+    parse_project_path(&url)
+        .ok_or_else(|| anyhow::anyhow!(fill(tr().remote_url_unparsable, &[&url])))
+}
+
+fn parse_project_path(url: &str) -> Option<String> {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        // scp-like `git@host:owner/name.git`
+        None => url.split_once(':')?.1,
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// Build the configured tracker, wrapped in the on-disk cache
+pub fn build_tracker(config: &TrackerConfig) -> anyhow::Result<Box<dyn IssueTracker>> {
+    let project = || match &config.project {
+        Some(project) => Ok(project.clone()),
+        None => project_from_origin(),
+    };
+    let inner: Box<dyn IssueTracker> = match config.kind {
+        TrackerKind::GitHub => Box::new(GitHubTracker {
+            base_url: config
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "https://api.github.com".into()),
+            repo: project()?,
+            token: config.token.clone(),
+        }),
+        TrackerKind::GitLab => Box::new(GitLabTracker {
+            base_url: config
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "https://gitlab.com".into()),
+            project: project()?,
+            token: config.token.clone(),
+        }),
+        TrackerKind::Jira => Box::new(JiraTracker {
+            base_url: config
+                .base_url
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!(tr().tracker_no_base_url))?,
+            token: config.token.clone(),
+            user: config.user.clone(),
+        }),
+        TrackerKind::Local => Box::new(LocalTracker {
+            path: config
+                .path
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!(tr().tracker_no_path))?,
+        }),
+    };
+    Ok(Box::new(CachedTracker::new(
+        inner,
+        cache_path()?,
+        config.cache_ttl_hours * 3600,
+    )))
+}
+
+fn cache_path() -> anyhow::Result<PathBuf> {
+    Ok(get_config_dir()?.join("cache").join("issues.json"))
+}
+
+/// Title for `issue`, or `None` with a warning when the lookup fails
+pub async fn lookup_issue_title(config: &TrackerConfig, issue: &str) -> Option<String> {
+    let result = match build_tracker(config) {
+        Ok(tracker) => tracker.issue_title(issue.trim()).await,
+        Err(err) => Err(err),
+    };
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", fill(tr().issue_lookup_failed, &[&issue, &err]));
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    #[async_trait]
+    impl IssueTracker for Failing {
+        fn cache_key(&self) -> String {
+            "failing".into()
+        }
+
+        async fn issue_title(&self, _id: &str) -> anyhow::Result<Option<String>> {
+            anyhow::bail!("offline")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_tracker() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("issues.json");
+        std::fs::write(
+            &path,
+            r#"{"123": "Crash on empty config", "PROJ-9": "Retry uploads"}"#,
+        )
+        .unwrap();
+        let tracker = LocalTracker { path };
+        assert_eq!(
+            tracker.issue_title("#123").await.unwrap().as_deref(),
+            Some("Crash on empty config")
+        );
+        assert_eq!(tracker.issue_title("404").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_entries_without_inner_tracker() {
+        let dir = tempfile::tempdir().unwrap();
+        let issues = dir.path().join("issues.json");
+        let cache = dir.path().join("cache.json");
+        std::fs::write(&issues, r#"{"1": "First"}"#).unwrap();
+
+        let local = CachedTracker::new(
+            Box::new(LocalTracker {
+                path: issues.clone(),
+            }),
+            cache.clone(),
+            3600,
+        );
+        assert_eq!(
+            local.issue_title("1").await.unwrap().as_deref(),
+            Some("First")
+        );
+
+        // Same cache key, source gone: the cached title is still returned
+        std::fs::remove_file(&issues).unwrap();
+        assert_eq!(
+            local.issue_title("1").await.unwrap().as_deref(),
+            Some("First")
+        );
+
+        let failing = CachedTracker::new(Box::new(Failing), cache, 3600);
+        assert!(failing.issue_title("1").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_project_path() {
+        assert_eq!(
+            parse_project_path("git@github.com:kitty-eu-org/aigcw.git").as_deref(),
+            Some("kitty-eu-org/aigcw")
+        );
+        assert_eq!(
+            parse_project_path("https://gitlab.com/group/sub/project").as_deref(),
+            Some("group/sub/project")
+        );
+    }
+}
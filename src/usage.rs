@@ -0,0 +1,258 @@
+use crate::app_config::{get_config_dir, LLMConfig, ModelPrice};
+use crate::git_utils::get_repo_root;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// One LLM call in the usage log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// Local time, RFC 3339
+    pub time: String,
+    pub provider: String,
+    pub model: String,
+    /// Name of the repository root directory
+    pub repo: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Counted from the text because the provider reported no usage
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+impl UsageEntry {
+    fn day(&self) -> &str {
+        self.time.get(..10).unwrap_or(&self.time)
+    }
+
+    /// USD, `None` when the model has no price
+    pub fn cost(&self, prices: &HashMap<String, ModelPrice>) -> Option<f64> {
+        prices.get(&self.model).map(|price| {
+            (self.prompt_tokens as f64 * price.input + self.completion_tokens as f64 * price.output)
+                / 1_000_000.0
+        })
+    }
+}
+
+pub fn usage_path() -> anyhow::Result<PathBuf> {
+    Ok(get_config_dir()?.join("usage.jsonl"))
+}
+
+/// Append a call to the usage log
+pub fn record(
+    llm_config: &LLMConfig,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    estimated: bool,
+) -> anyhow::Result<()> {
+    let entry = UsageEntry {
+        time: chrono::Local::now().to_rfc3339(),
+        provider: format!("{:?}", llm_config.provider),
+        model: llm_config.model.clone().unwrap_or_else(|| "default".into()),
+        repo: get_repo_root()
+            .ok()
+            .and_then(|root| root.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "-".into()),
+        prompt_tokens,
+        completion_tokens,
+        estimated,
+    };
+    let path = usage_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// Every recorded call, skipping lines that do not parse
+pub fn read_log() -> anyhow::Result<Vec<UsageEntry>> {
+    let content = std::fs::read_to_string(usage_path()?).unwrap_or_default();
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Day,
+    Model,
+    Repo,
+}
+
+impl GroupBy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "day" => Some(Self::Day),
+            "model" => Some(Self::Model),
+            "repo" => Some(Self::Repo),
+            _ => None,
+        }
+    }
+
+    fn key<'a>(&self, entry: &'a UsageEntry) -> &'a str {
+        match self {
+            Self::Day => entry.day(),
+            Self::Model => &entry.model,
+            Self::Repo => &entry.repo,
+        }
+    }
+}
+
+/// Totals of one group in `gcw usage`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageRow {
+    pub key: String,
+    pub calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Sum over the priced calls, `None` when none are
+    pub cost: Option<f64>,
+    /// Calls of models without a price, left out of `cost`
+    pub unpriced: usize,
+}
+
+impl UsageRow {
+    fn add(&mut self, entry: &UsageEntry, prices: &HashMap<String, ModelPrice>) {
+        self.calls += 1;
+        self.prompt_tokens += entry.prompt_tokens;
+        self.completion_tokens += entry.completion_tokens;
+        match entry.cost(prices) {
+            Some(cost) => *self.cost.get_or_insert(0.0) += cost,
+            None => self.unpriced += 1,
+        }
+    }
+}
+
+/// Group rows sorted by key, followed by the total over all entries
+pub fn summarize(
+    entries: &[UsageEntry],
+    by: GroupBy,
+    prices: &HashMap<String, ModelPrice>,
+) -> (Vec<UsageRow>, UsageRow) {
+    let mut groups: BTreeMap<&str, UsageRow> = BTreeMap::new();
+    let mut total = UsageRow::default();
+    for entry in entries {
+        let key = by.key(entry);
+        groups
+            .entry(key)
+            .or_insert_with(|| UsageRow {
+                key: key.to_string(),
+                ..Default::default()
+            })
+            .add(entry, prices);
+        total.add(entry, prices);
+    }
+    (groups.into_values().collect(), total)
+}
+
+/// Aligned table of `rows` and `total` under `columns` (key, calls, prompt, completion, cost).
+/// A cost that leaves out unpriced calls is marked with `+`.
+pub fn render(rows: &[UsageRow], total: &UsageRow, columns: &[&str]) -> String {
+    let width = rows
+        .iter()
+        .map(|row| row.key.chars().count())
+        .chain(std::iter::once(total.key.chars().count()))
+        .chain(columns.first().map(|c| c.chars().count()))
+        .max()
+        .unwrap_or_default();
+    let line = |cells: [&str; 5]| {
+        format!(
+            "{:<width$}  {:>6}  {:>12}  {:>12}  {:>10}\n",
+            cells[0],
+            cells[1],
+            cells[2],
+            cells[3],
+            cells[4],
+            width = width
+        )
+    };
+    let row_line = |row: &UsageRow| {
+        line([
+            &row.key,
+            &row.calls.to_string(),
+            &row.prompt_tokens.to_string(),
+            &row.completion_tokens.to_string(),
+            &match (row.cost, row.unpriced) {
+                (None, _) => "-".to_string(),
+                (Some(cost), 0) => format!("${:.4}", cost),
+                (Some(cost), _) => format!("${:.4}+", cost),
+            },
+        ])
+    };
+
+    let mut out = String::new();
+    if let [key, calls, prompt, completion, cost] = columns {
+        out.push_str(&line([key, calls, prompt, completion, cost]));
+    }
+    for row in rows {
+        out.push_str(&row_line(row));
+    }
+    out.push_str(&row_line(total));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: &str, model: &str, repo: &str, prompt: u64, completion: u64) -> UsageEntry {
+        UsageEntry {
+            time: time.into(),
+            provider: "OpenAI".into(),
+            model: model.into(),
+            repo: repo.into(),
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            estimated: false,
+        }
+    }
+
+    #[test]
+    fn test_summarize_and_render() {
+        let entries = vec![
+            entry("2026-03-01T10:00:00+01:00", "gpt-4o", "app", 1000, 100),
+            entry("2026-03-01T11:00:00+01:00", "llama3", "app", 500, 50),
+            entry("2026-03-02T09:00:00+01:00", "gpt-4o", "lib", 3000, 200),
+        ];
+        let prices = HashMap::from([(
+            "gpt-4o".to_string(),
+            ModelPrice {
+                input: 2.5,
+                output: 10.0,
+            },
+        )]);
+
+        let (rows, total) = summarize(&entries, GroupBy::Day, &prices);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].key, "2026-03-01");
+        assert_eq!(rows[0].calls, 2);
+        assert_eq!(rows[0].prompt_tokens, 1500);
+        assert_eq!(total.completion_tokens, 350);
+
+        let (rows, mut total) = summarize(&entries, GroupBy::Model, &prices);
+        assert_eq!(rows[1].key, "llama3");
+        assert_eq!(rows[1].cost, None);
+        assert_eq!(total.unpriced, 1);
+        total.key = "total".into();
+        assert_eq!(
+            render(
+                &rows,
+                &total,
+                &["model", "calls", "prompt", "completion", "cost"]
+            ),
+            "\
+model    calls        prompt    completion        cost
+gpt-4o       2          4000           300     $0.0130
+llama3       1           500            50           -
+total        3          4500           350    $0.0130+
+"
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use crate::app_config::WireFormat;
+use crate::customer_llm_backend::endpoint_url;
+use rllm::chat::{ChatMessage, ChatRole};
+use serde::{Deserialize, Serialize};
+
+/// Anthropic requires `max_tokens`; used when none is configured
+const ANTHROPIC_MAX_TOKENS: u32 = 1024;
+
+/// What a codec needs to build a chat request
+pub struct ChatRequest<'a> {
+    pub model: &'a str,
+    pub system: Option<&'a str>,
+    pub messages: &'a [ChatMessage],
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    /// Ignored by Gemini, which streams from a different endpoint
+    pub stream: Option<bool>,
+}
+
+/// Token counts reported by the endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt: u32,
+    pub completion: u32,
+}
+
+/// The reply of any wire format
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatReply {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+impl WireFormat {
+    /// The chat endpoint of this format for `url`, see [`endpoint_url`]
+    pub fn endpoint(&self, url: &str, model: &str) -> String {
+        let url = url.trim().trim_end_matches('/');
+        match self {
+            WireFormat::OpenAI => endpoint_url(url, "/v1", "/chat/completions"),
+            WireFormat::Anthropic => endpoint_url(url, "/v1", "/messages"),
+            WireFormat::Gemini if url.ends_with(":generateContent") => url.to_string(),
+            WireFormat::Gemini => endpoint_url(
+                url,
+                "/v1beta",
+                &format!("/models/{}:generateContent", model),
+            ),
+        }
+    }
+
+    /// The model list next to `endpoint`
+    pub fn models_url(&self, endpoint: &str) -> String {
+        let base = match self {
+            WireFormat::OpenAI => endpoint.strip_suffix("/chat/completions"),
+            WireFormat::Anthropic => endpoint.strip_suffix("/messages"),
+            WireFormat::Gemini => endpoint.rsplit_once("/models/").map(|(base, _)| base),
+        };
+        format!("{}/models", base.unwrap_or(endpoint))
+    }
+
+    /// Header carrying the API key, and whether it is a bearer token
+    pub fn auth_header(&self) -> (&'static str, bool) {
+        match self {
+            WireFormat::OpenAI => ("Authorization", true),
+            WireFormat::Anthropic => ("x-api-key", false),
+            WireFormat::Gemini => ("x-goog-api-key", false),
+        }
+    }
+
+    /// Headers the format always needs
+    pub fn fixed_headers(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            WireFormat::Anthropic => &[("anthropic-version", "2023-06-01")],
+            _ => &[],
+        }
+    }
+
+    pub fn encode(&self, request: &ChatRequest) -> serde_json::Result<serde_json::Value> {
+        match self {
+            WireFormat::OpenAI => serde_json::to_value(openai::Request::new(request)),
+            WireFormat::Anthropic => serde_json::to_value(anthropic::Request::new(request)),
+            WireFormat::Gemini => serde_json::to_value(gemini::Request::new(request)),
+        }
+    }
+
+    pub fn decode(&self, body: &str) -> serde_json::Result<ChatReply> {
+        Ok(match self {
+            WireFormat::OpenAI => serde_json::from_str::<openai::Response>(body)?.into(),
+            WireFormat::Anthropic => serde_json::from_str::<anthropic::Response>(body)?.into(),
+            WireFormat::Gemini => serde_json::from_str::<gemini::Response>(body)?.into(),
+        })
+    }
+
+    /// Model IDs from the body of `models_url`
+    pub fn decode_models(&self, body: &str) -> serde_json::Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Entry {
+            #[serde(alias = "name")]
+            id: String,
+        }
+        #[derive(Deserialize)]
+        struct List {
+            #[serde(alias = "models")]
+            data: Vec<Entry>,
+        }
+        let list: List = serde_json::from_str(body)?;
+        Ok(list
+            .data
+            .into_iter()
+            // Gemini names models `models/<id>`
+            .map(|entry| match entry.id.strip_prefix("models/") {
+                Some(id) => id.to_string(),
+                None => entry.id,
+            })
+            .collect())
+    }
+}
+
+fn role(message: &ChatMessage) -> &'static str {
+    match message.role {
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+    }
+}
+
+/// OpenAI chat completions
+mod openai {
+    use super::*;
+
+    /// Has no `top_k`, which OpenAI rejects
+    #[derive(Serialize)]
+    pub struct Request<'a> {
+        model: &'a str,
+        messages: Vec<Message<'a>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_tokens: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        temperature: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        top_p: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stream: Option<bool>,
+    }
+
+    #[derive(Serialize)]
+    struct Message<'a> {
+        role: &'a str,
+        content: &'a str,
+    }
+
+    impl<'a> Request<'a> {
+        pub fn new(request: &ChatRequest<'a>) -> Self {
+            let system = request.system.map(|content| Message {
+                role: "system",
+                content,
+            });
+            let messages = request.messages.iter().map(|m| Message {
+                role: role(m),
+                content: &m.content,
+            });
+            Self {
+                model: request.model,
+                messages: system.into_iter().chain(messages).collect(),
+                max_tokens: request.max_tokens,
+                temperature: request.temperature,
+                top_p: request.top_p,
+                stream: request.stream,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Response {
+        choices: Vec<Choice>,
+        #[serde(default)]
+        usage: Option<Usage>,
+    }
+
+    #[derive(Deserialize)]
+    struct Choice {
+        message: ChoiceMessage,
+    }
+
+    #[derive(Deserialize)]
+    struct ChoiceMessage {
+        #[serde(default)]
+        content: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Usage {
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    }
+
+    impl From<Response> for ChatReply {
+        fn from(response: Response) -> Self {
+            ChatReply {
+                text: response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|c| c.message.content)
+                    .unwrap_or_default(),
+                usage: response.usage.map(|u| TokenUsage {
+                    prompt: u.prompt_tokens,
+                    completion: u.completion_tokens,
+                }),
+            }
+        }
+    }
+}
+
+/// Anthropic Messages
+mod anthropic {
+    use super::*;
+
+    #[derive(Serialize)]
+    pub struct Request<'a> {
+        model: &'a str,
+        max_tokens: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        system: Option<&'a str>,
+        messages: Vec<Message<'a>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        temperature: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        top_p: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        top_k: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stream: Option<bool>,
+    }
+
+    #[derive(Serialize)]
+    struct Message<'a> {
+        role: &'a str,
+        content: &'a str,
+    }
+
+    impl<'a> Request<'a> {
+        pub fn new(request: &ChatRequest<'a>) -> Self {
+            Self {
+                model: request.model,
+                max_tokens: request.max_tokens.unwrap_or(ANTHROPIC_MAX_TOKENS),
+                system: request.system,
+                messages: request
+                    .messages
+                    .iter()
+                    .map(|m| Message {
+                        role: role(m),
+                        content: &m.content,
+                    })
+                    .collect(),
+                temperature: request.temperature,
+                top_p: request.top_p,
+                top_k: request.top_k,
+                stream: request.stream,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Response {
+        content: Vec<Block>,
+        #[serde(default)]
+        usage: Option<Usage>,
+    }
+
+    #[derive(Deserialize)]
+    struct Block {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+    }
+
+    impl From<Response> for ChatReply {
+        fn from(response: Response) -> Self {
+            ChatReply {
+                text: response
+                    .content
+                    .into_iter()
+                    .filter(|block| block.kind == "text")
+                    .map(|block| block.text)
+                    .collect(),
+                usage: response.usage.map(|u| TokenUsage {
+                    prompt: u.input_tokens,
+                    completion: u.output_tokens,
+                }),
+            }
+        }
+    }
+}
+
+/// Gemini generateContent
+mod gemini {
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Request<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        system_instruction: Option<Content<'a>>,
+        contents: Vec<Content<'a>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        generation_config: Option<GenerationConfig>,
+    }
+
+    #[derive(Serialize)]
+    struct Content<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        role: Option<&'a str>,
+        parts: Vec<Part<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct Part<'a> {
+        text: &'a str,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct GenerationConfig {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_output_tokens: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        temperature: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        top_p: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        top_k: Option<u32>,
+    }
+
+    impl<'a> Request<'a> {
+        pub fn new(request: &ChatRequest<'a>) -> Self {
+            let config = GenerationConfig {
+                max_output_tokens: request.max_tokens,
+                temperature: request.temperature,
+                top_p: request.top_p,
+                top_k: request.top_k,
+            };
+            let is_empty = config.max_output_tokens.is_none()
+                && config.temperature.is_none()
+                && config.top_p.is_none()
+                && config.top_k.is_none();
+            Self {
+                system_instruction: request.system.map(|text| Content {
+                    role: None,
+                    parts: vec![Part { text }],
+                }),
+                contents: request
+                    .messages
+                    .iter()
+                    .map(|m| Content {
+                        // Gemini calls the assistant `model`
+                        role: Some(match m.role {
+                            ChatRole::User => "user",
+                            ChatRole::Assistant => "model",
+                        }),
+                        parts: vec![Part { text: &m.content }],
+                    })
+                    .collect(),
+                generation_config: (!is_empty).then_some(config),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Response {
+        #[serde(default)]
+        candidates: Vec<Candidate>,
+        #[serde(default)]
+        usage_metadata: Option<UsageMetadata>,
+    }
+
+    #[derive(Deserialize)]
+    struct Candidate {
+        #[serde(default)]
+        content: Option<CandidateContent>,
+    }
+
+    #[derive(Deserialize)]
+    struct CandidateContent {
+        #[serde(default)]
+        parts: Vec<CandidatePart>,
+    }
+
+    #[derive(Deserialize)]
+    struct CandidatePart {
+        #[serde(default)]
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct UsageMetadata {
+        #[serde(default)]
+        prompt_token_count: u32,
+        #[serde(default)]
+        candidates_token_count: u32,
+    }
+
+    impl From<Response> for ChatReply {
+        fn from(response: Response) -> Self {
+            ChatReply {
+                text: response
+                    .candidates
+                    .into_iter()
+                    .next()
+                    .and_then(|c| c.content)
+                    .map(|content| content.parts.into_iter().map(|p| p.text).collect())
+                    .unwrap_or_default(),
+                usage: response.usage_metadata.map(|u| TokenUsage {
+                    prompt: u.prompt_token_count,
+                    completion: u.candidates_token_count,
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rllm::chat::MessageType;
+
+    const SYSTEM: &str = "You write concise conventional commit messages.";
+    const USER: &str = "Write a commit message for:\n\n+fn parse() {}\n";
+
+    fn assert_codec(format: WireFormat, model: &str, request: &str, response: &str) {
+        let messages = [ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::default(),
+            content: USER.to_string(),
+        }];
+        let encoded = format
+            .encode(&ChatRequest {
+                model,
+                system: Some(SYSTEM),
+                messages: &messages,
+                max_tokens: None,
+                temperature: None,
+                top_p: None,
+                top_k: None,
+                stream: None,
+            })
+            .unwrap();
+        let expected: serde_json::Value = serde_json::from_str(request).unwrap();
+        assert_eq!(encoded, expected, "{:?} request", format);
+
+        let reply = format.decode(response).unwrap();
+        assert_eq!(
+            reply.text, "feat: add parser entry point",
+            "{:?} reply",
+            format
+        );
+        assert!(reply
+            .usage
+            .is_some_and(|u| u.prompt > 0 && u.completion > 0));
+    }
+
+    #[test]
+    fn test_codecs_match_fixtures() {
+        assert_codec(
+            WireFormat::OpenAI,
+            "gpt-4o-mini",
+            include_str!("fixtures/wire/openai.request.json"),
+            include_str!("fixtures/wire/openai.response.json"),
+        );
+        assert_codec(
+            WireFormat::Anthropic,
+            "claude-3-5-haiku-20241022",
+            include_str!("fixtures/wire/anthropic.request.json"),
+            include_str!("fixtures/wire/anthropic.response.json"),
+        );
+        assert_codec(
+            WireFormat::Gemini,
+            "gemini-2.0-flash",
+            include_str!("fixtures/wire/gemini.request.json"),
+            include_str!("fixtures/wire/gemini.response.json"),
+        );
+    }
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            WireFormat::Anthropic.endpoint("https://gateway", "m"),
+            "https://gateway/v1/messages"
+        );
+        let gemini = WireFormat::Gemini.endpoint("https://gateway/v1beta", "gemini-2.0-flash");
+        assert_eq!(
+            gemini,
+            "https://gateway/v1beta/models/gemini-2.0-flash:generateContent"
+        );
+        assert_eq!(
+            WireFormat::Gemini.endpoint(&gemini, "other"),
+            gemini,
+            "a full endpoint is kept"
+        );
+        assert_eq!(
+            WireFormat::Gemini.models_url(&gemini),
+            "https://gateway/v1beta/models"
+        );
+        assert_eq!(
+            WireFormat::OpenAI.models_url("https://api.example.com/v1/chat/completions"),
+            "https://api.example.com/v1/models"
+        );
+    }
+
+    #[test]
+    fn test_decode_models() {
+        assert_eq!(
+            WireFormat::OpenAI
+                .decode_models(r#"{"object":"list","data":[{"id":"gpt-4o","object":"model"}]}"#)
+                .unwrap(),
+            ["gpt-4o"]
+        );
+        assert_eq!(
+            WireFormat::Gemini
+                .decode_models(r#"{"models":[{"name":"models/gemini-2.0-flash","version":"2.0"}]}"#)
+                .unwrap(),
+            ["gemini-2.0-flash"]
+        );
+    }
+}